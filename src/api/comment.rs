@@ -1,8 +1,9 @@
 use async_trait::async_trait;
 
 use crate::{
-    error::{Result, TikTokError},
+    error::{ApiStatus, Result, TikTokError},
     types::{Comment, RequestParams},
+    endpoints::Endpoint,
     TikTokApi,
 };
 
@@ -21,7 +22,7 @@ impl CommentApi for TikTokApi {
         };
 
         let response = self.make_request(
-            "https://www.tiktok.com/api/comment/detail/",
+            &self.endpoint_url(Endpoint::CommentDetail),
             Some(params),
             None,
             None
@@ -31,7 +32,7 @@ impl CommentApi for TikTokApi {
             serde_json::from_value(comment_data.clone())
                 .map_err(|e| e.into())
         } else {
-            Err(TikTokError::NotFound)
+            Err(TikTokError::NotFound(ApiStatus::missing(Endpoint::CommentDetail.path())))
         }
     }
 
@@ -48,7 +49,7 @@ impl CommentApi for TikTokApi {
             };
 
             let response = self.make_request(
-                "https://www.tiktok.com/api/comment/list/reply/",
+                &self.endpoint_url(Endpoint::CommentReplies),
                 Some(params),
                 None,
                 None
@@ -1,8 +1,9 @@
 use async_trait::async_trait;
 
 use crate::{
-    error::{Result, TikTokError},
+    error::{ApiStatus, Result, TikTokError},
     types::{Hashtag, Video, RequestParams},
+    endpoints::Endpoint,
     TikTokApi,
 };
 
@@ -21,7 +22,7 @@ impl HashtagApi for TikTokApi {
         };
 
         let response = self.make_request(
-            "https://www.tiktok.com/api/challenge/detail/",
+            &self.endpoint_url(Endpoint::ChallengeDetail),
             Some(params),
             None,
             None
@@ -29,7 +30,7 @@ impl HashtagApi for TikTokApi {
 
         let challenge_info = response["challengeInfo"].clone();
         if challenge_info.is_null() {
-            return Err(TikTokError::NotFound);
+            return Err(TikTokError::NotFound(ApiStatus::missing(Endpoint::ChallengeDetail.path())));
         }
 
         serde_json::from_value(challenge_info)
@@ -49,7 +50,7 @@ impl HashtagApi for TikTokApi {
             };
 
             let response = self.make_request(
-                "https://www.tiktok.com/api/challenge/item_list/",
+                &self.endpoint_url(Endpoint::ChallengeItems),
                 Some(params),
                 None,
                 None
@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use crate::{
     types::{UserProfile, Video, RequestParams},
     error::Result,
+    endpoints::Endpoint,
     TikTokApi,
 };
 
@@ -51,22 +52,22 @@ impl SearchApi for TikTokApi {
         let mut results = Vec::new();
         let mut cursor = 0;
 
-        let type_path = match obj_type {
-            SearchType::User => "user",
-            SearchType::Video => "video",
+        let endpoint = match obj_type {
+            SearchType::User => Endpoint::SearchUser,
+            SearchType::Video => Endpoint::SearchVideo,
         };
 
         while results.len() < count {
             let params = RequestParams {
                 keyword: Some(query.to_string()),
                 cursor: Some(cursor.to_string()),
-                from_page: "search".to_string(),
+                from_page: Some("search".to_string()),
                 web_search_code: Some(r#"{"tiktok":{"client_params_x":{"search_engine":{"ies_mt_user_live_video_card_use_libra":1,"mt_search_general_user_live_card":1}},"search_server":{}}}"#.to_string()),
                 ..Default::default()
             };
 
             let response = self.make_request(
-                &format!("https://www.tiktok.com/api/search/{}/full/", type_path),
+                &self.endpoint_url(endpoint),
                 Some(params),
                 None,
                 None
@@ -1,8 +1,9 @@
 use async_trait::async_trait;
 
 use crate::{
-    error::{Result, TikTokError},
+    error::{ApiStatus, Result, TikTokError},
     types::{Sound, Video, RequestParams},
+    endpoints::Endpoint,
     TikTokApi,
 };
 
@@ -21,7 +22,7 @@ impl SoundApi for TikTokApi {
         };
 
         let response = self.make_request(
-            "https://www.tiktok.com/api/music/detail/",
+            &self.endpoint_url(Endpoint::MusicDetail),
             Some(params),
             None,
             None
@@ -31,7 +32,7 @@ impl SoundApi for TikTokApi {
             serde_json::from_value(music_info.clone())
                 .map_err(|e| e.into())
         } else {
-            Err(TikTokError::NotFound)
+            Err(TikTokError::NotFound(ApiStatus::missing(Endpoint::MusicDetail.path())))
         }
     }
 
@@ -48,7 +49,7 @@ impl SoundApi for TikTokApi {
             };
 
             let response = self.make_request(
-                "https://www.tiktok.com/api/music/item_list/",
+                &self.endpoint_url(Endpoint::MusicItems),
                 Some(params),
                 None,
                 None
@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use crate::{
     error::Result,
     types::{Video, RequestParams},
+    endpoints::Endpoint,
     TikTokApi,
 };
 
@@ -18,13 +19,13 @@ impl TrendingApi for TikTokApi {
 
         while videos.len() < count {
             let params = RequestParams {
-                from_page: "fyp".to_string(),
+                from_page: Some("fyp".to_string()),
                 count: Some(count.to_string()),
                 ..Default::default()
             };
 
             let response = self.make_request(
-                "https://www.tiktok.com/api/recommend/item_list/",
+                &self.endpoint_url(Endpoint::Recommend),
                 Some(params),
                 None,
                 None
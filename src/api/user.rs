@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use crate::{
     error::Result,
     types::{UserProfile, Video, RequestParams},
+    endpoints::Endpoint,
     TikTokApi,
 };
 
@@ -23,13 +24,13 @@ impl UserApi for TikTokApi {
         };
 
         let response = self.make_request(
-            "https://www.tiktok.com/api/user/detail/",
+            &self.endpoint_url(Endpoint::UserDetail),
             Some(params),
             None,
             None,
         ).await?;
 
-        serde_json::from_value(response["userInfo"].clone())
+        serde_json::from_value(response["userInfo"]["user"].clone())
             .map_err(|e| e.into())
     }
 
@@ -46,7 +47,7 @@ impl UserApi for TikTokApi {
             };
 
             let response = self.make_request(
-                "https://www.tiktok.com/api/post/item_list/",
+                &self.endpoint_url(Endpoint::UserPosts),
                 Some(params),
                 None,
                 None,
@@ -88,7 +89,7 @@ impl UserApi for TikTokApi {
             };
 
             let response = self.make_request(
-                "https://www.tiktok.com/api/favorite/item_list",
+                &self.endpoint_url(Endpoint::UserLiked),
                 Some(params),
                 None,
                 None,
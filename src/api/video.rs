@@ -4,6 +4,7 @@ use reqwest::Url;
 use crate::{
     error::{Result, TikTokError},
     types::{Video, Comment, RequestParams},
+    endpoints::{self, Endpoint},
     TikTokApi,
 };
 
@@ -25,7 +26,7 @@ impl VideoApi for TikTokApi {
         };
 
         let response = self.make_request(
-            "https://www.tiktok.com/api/item/detail/",
+            &self.endpoint_url(Endpoint::ItemDetail),
             Some(params),
             None,
             None
@@ -48,24 +49,11 @@ impl VideoApi for TikTokApi {
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert("range", "bytes=0-".parse().unwrap());
         headers.insert("accept-encoding", "identity;q=1, *;q=0".parse().unwrap());
-        headers.insert("referer", "https://www.tiktok.com/".parse().unwrap());
+        let referer = endpoints::resolve(&self.config.base_url, "/");
+        headers.insert("referer", referer.parse().map_err(|_| TikTokError::InvalidUrl(referer))?);
         
-        // Build client
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
-
-        // Make request
-        let response = client
-            .get(download_url)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(TikTokError::RequestError(response.error_for_status().unwrap_err()));
-        }
-
-        Ok(response.bytes().await?.to_vec())
+        // Download through a session's proxy
+        self.download(download_url, headers).await
     }
 
     async fn video_comments(&self, video_id: &str, count: usize) -> Result<Vec<Comment>> {
@@ -81,7 +69,7 @@ impl VideoApi for TikTokApi {
             };
 
             let response = self.make_request(
-                "https://www.tiktok.com/api/comment/list/",
+                &self.endpoint_url(Endpoint::CommentList),
                 Some(params),
                 None,
                 None
@@ -125,7 +113,7 @@ impl VideoApi for TikTokApi {
         };
 
         let response = self.make_request(
-            "https://www.tiktok.com/api/related/item_list/",
+            &self.endpoint_url(Endpoint::RelatedItems),
             Some(params),
             None,
             None
@@ -0,0 +1,366 @@
+use chromiumoxide::{Browser, BrowserConfig, Handler, Page};
+use chromiumoxide::cdp::browser_protocol::{
+    browser::{BrowserContextId, CloseParams},
+    fetch::{
+        AuthChallengeResponse, AuthChallengeResponseResponse, AuthChallengeSource, ContinueRequestParams,
+        ContinueWithAuthParams, EnableParams, EventAuthRequired, EventRequestPaused, RequestPattern,
+    },
+    network::{Cookie, CookieParam, ResourceType, SetCookiesParams, TimeSinceEpoch},
+    target::{CreateBrowserContextParams, CreateTargetParams},
+};
+use futures::StreamExt;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use url::Url;
+
+use crate::{
+    error::{Result, TikTokError},
+    proxy::ProxyConfig,
+    types::{SessionConfig, TikTokSession},
+    TikTokApiConfig,
+};
+
+const SDK_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Proxy challenges remembered to spot credentials the proxy rejected.
+const MAX_ANSWERED_CHALLENGES: usize = 64;
+
+/// Launches a browser for one session, or a shared one hosting several session contexts when
+/// `session` is `None`; proxies of shared browsers are set per context instead.
+pub(crate) async fn launch(config: &TikTokApiConfig, session: Option<&SessionConfig>, profile: Option<&Path>) -> Result<Browser> {
+    let launch = &config.launch;
+    let mut config_builder = BrowserConfig::builder()
+        .launch_timeout(launch.launch_timeout)
+        .args(config.browser_args.iter().flatten());
+    let window_size = launch.window_size
+        .or_else(|| session.map(|session| (session.screen_width, session.screen_height)));
+    if let Some((width, height)) = window_size {
+        config_builder = config_builder.window_size(width, height);
+    }
+    if !config.headless {
+        config_builder = config_builder.with_head();
+    }
+    if !launch.sandbox {
+        config_builder = config_builder.no_sandbox();
+    }
+    if let Some(executable) = &launch.executable {
+        config_builder = config_builder.chrome_executable(executable);
+    }
+    if let Some(profile) = profile {
+        std::fs::create_dir_all(profile)
+            .map_err(|e| TikTokError::BrowserError(format!("cannot create profile {}: {}", profile.display(), e)))?;
+        config_builder = config_builder.user_data_dir(profile);
+    }
+    if let Some(proxy) = session.map(browser_proxy).transpose()?.flatten() {
+        config_builder = config_builder.arg("--proxy-server=".to_string() + &proxy.server());
+    }
+    let browser_config = config_builder.build()?;
+
+    let (browser, handler) = Browser::launch(browser_config).await?;
+    spawn_handler(handler);
+
+    Ok(browser)
+}
+
+/// Attaches to a browser someone else started, by `ws://` DevTools URL or `http://host:port`.
+pub(crate) async fn connect(endpoint: &str) -> Result<Browser> {
+    let (browser, handler) = Browser::connect(endpoint).await
+        .map_err(|e| TikTokError::BrowserError(format!("cannot connect to {}: {}", endpoint, e)))?;
+    spawn_handler(handler);
+
+    Ok(browser)
+}
+
+/// Opens a page in a new browser context, so the session gets its own cookies and proxy.
+/// The context is disposed by Chrome if our connection goes away.
+pub(crate) async fn open_context(browser: &Browser, session: &SessionConfig) -> Result<(BrowserContextId, Page)> {
+    let mut params = CreateBrowserContextParams::builder().dispose_on_detach(true);
+    if let Some(proxy) = browser_proxy(session)? {
+        params = params.proxy_server(proxy.server());
+    }
+    let context = browser.create_browser_context(params.build()).await?;
+
+    let target = CreateTargetParams::builder()
+        .url("about:blank")
+        .browser_context_id(context.clone())
+        .build()?;
+    let page = browser.new_page(target).await?;
+
+    Ok((context, page))
+}
+
+/// Closes the session's page and context, and its browser if we launched it.
+/// A launched browser that does not exit within `CLOSE_TIMEOUT` is killed. Every step runs
+/// even if an earlier one failed; the first error is returned.
+pub(crate) async fn shutdown(session: Arc<TikTokSession>) -> Result<()> {
+    let mut result = Ok(());
+    if let Some(page) = &session.page {
+        result = result.and(within_timeout("closing page", Page::clone(page).close()).await);
+    }
+    if let (Some(browser), Some(context)) = (&session.browser, session.context.clone()) {
+        result = result.and(within_timeout("disposing context", browser.dispose_browser_context(context)).await);
+    }
+    if !session.owns_browser {
+        return result;
+    }
+
+    let has_context = session.context.is_some();
+    let browser = match Arc::try_unwrap(session) {
+        Ok(session) => session.browser,
+        Err(shared) => shared.browser.clone(),
+    };
+    match browser.map(Arc::try_unwrap) {
+        Some(Ok(mut browser)) => result.and(close_browser(&mut browser).await),
+        // Other sessions still live in this browser; the last one to go closes it
+        Some(Err(_)) if has_context => result,
+        // Still leased by an in-flight request; the process is killed when the last handle drops
+        Some(Err(shared)) => result.and(within_timeout("closing browser", shared.execute(CloseParams::default())).await.map(drop)),
+        None => result,
+    }
+}
+
+async fn close_browser(browser: &mut Browser) -> Result<()> {
+    let closed = tokio::time::timeout(CLOSE_TIMEOUT, async {
+        browser.close().await?;
+        browser.wait().await.map_err(|e| TikTokError::BrowserError(e.to_string()))?;
+        Ok::<_, TikTokError>(())
+    }).await;
+
+    match closed {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            log::warn!("Browser did not close cleanly ({}), killing it", e);
+            kill(browser).await
+        }
+        Err(_) => {
+            log::warn!("Browser did not exit within {:?}, killing it", CLOSE_TIMEOUT);
+            kill(browser).await
+        }
+    }
+}
+
+async fn kill(browser: &mut Browser) -> Result<()> {
+    if let Some(Err(e)) = browser.kill().await {
+        return Err(TikTokError::BrowserError(format!("cannot kill browser: {}", e)));
+    }
+    // Reap the process so it does not linger as a zombie
+    let _ = browser.wait().await;
+    Ok(())
+}
+
+async fn within_timeout<T>(
+    step: &str,
+    future: impl Future<Output = std::result::Result<T, chromiumoxide::error::CdpError>>,
+) -> Result<T> {
+    match tokio::time::timeout(CLOSE_TIMEOUT, future).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(TikTokError::BrowserError(format!("{} timed out after {:?}", step, CLOSE_TIMEOUT))),
+    }
+}
+
+fn spawn_handler(mut handler: Handler) {
+    // Handle browser events in background, until the connection fails; commands sent after that
+    // error out instead of waiting on a browser that is gone
+    tokio::spawn(async move {
+        while let Some(event) = handler.next().await {
+            if let Err(e) = event {
+                log::warn!("Browser connection lost: {}", e);
+                break;
+            }
+        }
+    });
+}
+
+/// The session's proxy, refusing credentials Chrome has no way to use.
+fn browser_proxy(session: &SessionConfig) -> Result<Option<ProxyConfig>> {
+    let Some(proxy) = session.proxy.as_deref().map(ProxyConfig::parse).transpose()? else {
+        return Ok(None);
+    };
+    if proxy.is_socks() && proxy.credentials().is_some() {
+        return Err(TikTokError::BrowserError(format!(
+            "Chrome cannot authenticate to SOCKS proxies ({}); use an HTTP proxy or the HTTP transport",
+            proxy.address
+        )));
+    }
+    Ok(Some(proxy))
+}
+
+/// Proxy credentials a browser answers auth challenges with, by proxy address, since Chrome
+/// ignores credentials in `--proxy-server`. Challenges are handled on the browser's own DevTools
+/// session: chromiumoxide answers the ones raised on page sessions itself, without credentials.
+#[derive(Clone, Default)]
+pub(crate) struct ProxyAuth {
+    credentials: Arc<Mutex<HashMap<String, (String, String)>>>,
+    enabled: Arc<OnceCell<()>>,
+}
+
+impl ProxyAuth {
+    /// Adds the session's proxy credentials, turning challenge handling on for the first ones.
+    /// Does nothing for proxies without credentials.
+    pub(crate) async fn register(&self, browser: &Arc<Browser>, session: &SessionConfig) -> Result<()> {
+        let Some(proxy) = browser_proxy(session)? else {
+            return Ok(());
+        };
+        let Some((username, password)) = proxy.credentials() else {
+            return Ok(());
+        };
+        self.credentials.lock().unwrap()
+            .insert(proxy.address.clone(), (username.to_string(), password.to_string()));
+        self.enabled.get_or_try_init(|| self.enable(browser)).await?;
+        Ok(())
+    }
+
+    async fn enable(&self, browser: &Arc<Browser>) -> Result<()> {
+        let mut paused = browser.event_listener::<EventRequestPaused>().await?;
+        let mut challenges = browser.event_listener::<EventAuthRequired>().await?;
+        // Chrome keeps proxy credentials once they worked, so answering the challenge of a navigation
+        // covers the requests it makes; only navigations are paused
+        browser.execute(EnableParams::builder()
+            .handle_auth_requests(true)
+            .pattern(RequestPattern::builder().url_pattern("*").resource_type(ResourceType::Document).build())
+            .build()).await?;
+
+        // The tasks must not keep the browser alive, or it would never be closed
+        let weak = Arc::downgrade(browser);
+        tokio::spawn(async move {
+            while let Some(event) = paused.next().await {
+                let Some(browser) = weak.upgrade() else {
+                    break;
+                };
+                let _ = browser.execute(ContinueRequestParams::new(event.request_id.clone())).await;
+            }
+        });
+
+        let weak = Arc::downgrade(browser);
+        let credentials = self.credentials.clone();
+        tokio::spawn(async move {
+            let mut answered = VecDeque::new();
+            while let Some(event) = challenges.next().await {
+                let Some(browser) = weak.upgrade() else {
+                    break;
+                };
+                let proxy = Some(&event.auth_challenge)
+                    .filter(|challenge| challenge.source == Some(AuthChallengeSource::Proxy))
+                    .and_then(|challenge| Url::parse(&challenge.origin).ok())
+                    .and_then(|origin| Some(format!("{}:{}", origin.host_str()?, origin.port_or_known_default()?)));
+                let known = proxy.as_ref().and_then(|proxy| credentials.lock().unwrap().get(proxy).cloned());
+
+                // Credentials that were already rejected once are not offered again
+                let response = match known {
+                    None => AuthChallengeResponse::new(AuthChallengeResponseResponse::Default),
+                    Some(_) if answered.contains(&event.request_id) => {
+                        log::warn!("Proxy {} rejected the configured credentials", proxy.unwrap_or_default());
+                        AuthChallengeResponse::new(AuthChallengeResponseResponse::CancelAuth)
+                    }
+                    Some((username, password)) => {
+                        if answered.len() >= MAX_ANSWERED_CHALLENGES {
+                            answered.pop_front();
+                        }
+                        answered.push_back(event.request_id.clone());
+                        let mut response = AuthChallengeResponse::new(AuthChallengeResponseResponse::ProvideCredentials);
+                        response.username = Some(username);
+                        response.password = Some(password);
+                        response
+                    }
+                };
+                let _ = browser.execute(ContinueWithAuthParams::new(event.request_id.clone(), response)).await;
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Brings a fresh page onto TikTok: applies the msToken cookie, navigates to `base_url`,
+/// waits for the signing SDK and records the harvested msToken (and the real user agent if none is emulated).
+///
+/// Fails when the SDK does not load within `warmup_timeout`, so the page never joins the pool.
+pub(crate) async fn warm_up(page: &Page, config: &TikTokApiConfig, session: &mut SessionConfig) -> Result<()> {
+    if let Some(ms_token) = &session.ms_token {
+        let cookie = CookieParam::builder()
+            .name("msToken")
+            .value(ms_token.clone())
+            .url(config.base_url.clone())
+            .build()
+            .map_err(TikTokError::BrowserError)?;
+        page.set_cookie(cookie).await?;
+    }
+
+    page.goto(config.base_url.as_str()).await?;
+    page.wait_for_navigation().await?;
+
+    if !wait_for_sdk(page, config.warmup_timeout).await? {
+        return Err(TikTokError::BrowserError(format!(
+            "signing SDK did not load within {:?}",
+            config.warmup_timeout
+        )));
+    }
+
+    let cookies = read_cookies(page).await?;
+    if let Some(ms_token) = ms_token(&cookies) {
+        session.ms_token = Some(ms_token.to_string());
+    }
+    session.cookies = Some(cookies);
+    if session.user_agent.is_none() {
+        session.user_agent = Some(page.user_agent().await?);
+    }
+
+    Ok(())
+}
+
+/// Puts previously saved cookies back into a fresh page before it first loads TikTok, each on
+/// the domain and path it was set for.
+pub(crate) async fn restore_cookies(page: &Page, session: &SessionConfig) -> Result<()> {
+    let Some(cookies) = session.cookies.as_ref().filter(|cookies| !cookies.is_empty()) else {
+        return Ok(());
+    };
+
+    let params = cookies
+        .iter()
+        .map(|cookie| CookieParam {
+            domain: Some(cookie.domain.clone()),
+            path: Some(cookie.path.clone()),
+            secure: Some(cookie.secure),
+            http_only: Some(cookie.http_only),
+            same_site: cookie.same_site.clone(),
+            // Session cookies carry a placeholder expiry, which would make them persistent
+            expires: (!cookie.session).then(|| TimeSinceEpoch::new(cookie.expires)),
+            ..CookieParam::new(cookie.name.clone(), cookie.value.clone())
+        })
+        .collect();
+    // `Page::set_cookies` refuses blank pages, and the domain is already set on every cookie
+    page.execute(SetCookiesParams::new(params)).await?;
+
+    Ok(())
+}
+
+pub(crate) async fn read_cookies(page: &Page) -> Result<Vec<Cookie>> {
+    Ok(page.get_cookies().await?)
+}
+
+/// The msToken TikTok handed out, if it is among `cookies`.
+pub(crate) fn ms_token(cookies: &[Cookie]) -> Option<&str> {
+    cookies.iter().find(|cookie| cookie.name == "msToken").map(|cookie| cookie.value.as_str())
+}
+
+async fn wait_for_sdk(page: &Page, timeout: Duration) -> Result<bool> {
+    let started = Instant::now();
+    loop {
+        let loaded: bool = page
+            .evaluate("typeof window.byted_acrawler !== 'undefined'")
+            .await?
+            .into_value()
+            .unwrap_or(false);
+        if loaded {
+            return Ok(true);
+        }
+        if started.elapsed() >= timeout {
+            return Ok(false);
+        }
+        tokio::time::sleep(SDK_POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,141 @@
+use async_trait::async_trait;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{Result, TikTokError},
+    types::TikTokSession,
+};
+
+const CAPTCHA_SELECTOR: &str = "#captcha_container, .captcha_verify_container, #tiktok-verify-ele, .captcha-verify-container";
+
+/// A captcha that interrupted a request, handed to the registered `CaptchaHandler`.
+pub struct CaptchaChallenge<'a> {
+    pub session_index: usize,
+    pub url: &'a str,
+    pub subtype: Option<&'a str>,
+    pub session: &'a TikTokSession,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaResolution {
+    /// The captcha was cleared; the request is resent on the same session.
+    Solved,
+    /// Quarantine the session and resend the request on another one.
+    RotateSession,
+    /// Fail the request with `TikTokError::CaptchaRequired`.
+    GiveUp,
+}
+
+#[async_trait]
+pub trait CaptchaHandler: Send + Sync {
+    async fn handle(&self, challenge: &CaptchaChallenge<'_>) -> Result<CaptchaResolution>;
+}
+
+impl fmt::Debug for dyn CaptchaHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CaptchaHandler")
+    }
+}
+
+/// Rotates away from any session that hits a captcha.
+#[derive(Debug, Default, Clone)]
+pub struct RotateSessionHandler;
+
+#[async_trait]
+impl CaptchaHandler for RotateSessionHandler {
+    async fn handle(&self, _challenge: &CaptchaChallenge<'_>) -> Result<CaptchaResolution> {
+        Ok(CaptchaResolution::RotateSession)
+    }
+}
+
+/// Waits for someone to solve the captcha by hand in a headful session window.
+#[derive(Debug, Clone)]
+pub struct ManualSolveHandler {
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for ManualSolveHandler {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(300),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaHandler for ManualSolveHandler {
+    async fn handle(&self, challenge: &CaptchaChallenge<'_>) -> Result<CaptchaResolution> {
+        let Some(page) = challenge.session.page.as_ref() else {
+            return Ok(CaptchaResolution::RotateSession);
+        };
+
+        page.reload().await?;
+        page.bring_to_front().await?;
+        log::warn!("Captcha on session {}, waiting for it to be solved in the browser window", challenge.session_index);
+
+        let started = Instant::now();
+        let script = format!(
+            "!document.querySelector({}) && !location.href.includes('verify')",
+            serde_json::to_string(CAPTCHA_SELECTOR)?
+        );
+        while started.elapsed() < self.timeout {
+            let cleared: bool = page.evaluate(script.as_str()).await?.into_value().unwrap_or(false);
+            if cleared {
+                return Ok(CaptchaResolution::Solved);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+
+        Ok(CaptchaResolution::GiveUp)
+    }
+}
+
+/// Strings that only appear on TikTok's verify page: its URLs and the captcha container ids.
+const VERIFY_PAGE_MARKERS: [&str; 5] = [
+    "verify-center",
+    "verifycenter",
+    "/verify?",
+    "id=\"captcha_container\"",
+    "id=\"captcha-verify-image\"",
+];
+
+/// Recognises TikTok's verify/captcha answers and the verify-center redirect.
+pub(crate) fn detect(body: &str, session_index: usize, url: &str) -> Option<TikTokError> {
+    let captcha = |subtype: Option<String>| TikTokError::CaptchaRequired {
+        session_index,
+        url: url.to_string(),
+        subtype,
+    };
+
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(json) => {
+            let is_verify = json.get("type").and_then(|t| t.as_str()) == Some("verify")
+                || json.get("verify_event").is_some()
+                || json.get("fp").and_then(|fp| fp.as_str()).is_some_and(|fp| fp.starts_with("verify_"));
+            is_verify.then(|| captcha(json.get("subtype").and_then(|s| s.as_str()).map(str::to_string)))
+        }
+        Err(_) => {
+            // Only the verify page's own markers: ordinary pages may well mention "captcha"
+            let lower = body.to_ascii_lowercase();
+            let is_verify = VERIFY_PAGE_MARKERS.iter().any(|marker| lower.contains(marker));
+            is_verify.then(|| captcha(None))
+        }
+    }
+}
+
+/// Recognises a request that was redirected to the verify center.
+pub(crate) fn detect_redirect(final_url: &str, session_index: usize, url: &str) -> Option<TikTokError> {
+    let final_url = url::Url::parse(final_url).ok()?;
+    let redirected = final_url.path().starts_with("/verify")
+        || final_url.host_str().is_some_and(|host| host.contains("verify"));
+    redirected.then(|| TikTokError::CaptchaRequired {
+        session_index,
+        url: url.to_string(),
+        subtype: None,
+    })
+}
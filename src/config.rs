@@ -0,0 +1,687 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    captcha::CaptchaHandler,
+    endpoints::EndpointClass,
+    error::{Result, TikTokError},
+    fingerprint::FingerprintProfile,
+    proxy::{ProxyConfig, ProxyPoolConfig, ProxyStickiness},
+    rate_limit::{Rate, RateLimitConfig},
+    retry::RetryPolicy,
+    signer::SignerKind,
+    transport::TransportKind,
+};
+
+const DEVTOOLS_SCHEMES: [&str; 4] = ["ws", "wss", "http", "https"];
+
+#[derive(Debug, Clone)]
+pub struct TikTokApiConfig {
+    pub num_sessions: usize,
+    pub headless: bool,
+    pub ms_tokens: Option<Vec<String>>,
+    pub proxies: Option<Vec<String>>,
+    /// How proxies are scored, assigned and rotated.
+    pub proxy_pool: ProxyPoolConfig,
+    pub base_url: String,
+    /// Extra Chrome command line switches, added to every session browser.
+    pub browser_args: Option<Vec<String>>,
+    pub launch: LaunchOptions,
+    /// DevTools endpoints of browsers that are already running (`ws://...` or `http://host:9222`).
+    /// When set, sessions open in their own browser context on these, round robin, instead of
+    /// launching Chrome, and `close()` leaves the browsers running.
+    pub connect: Option<Vec<String>>,
+    pub transport: TransportKind,
+    pub signer: SignerKind,
+    pub warmup_timeout: Duration,
+    pub health_check_interval: Option<Duration>,
+    pub max_session_failures: u32,
+    pub retry: RetryPolicy,
+    pub rate_limits: RateLimitConfig,
+    pub captcha_handler: Option<Arc<dyn CaptchaHandler>>,
+    /// Profiles to pick from per session; `None` uses `FingerprintProfile::builtin()`.
+    pub fingerprints: Option<Vec<FingerprintProfile>>,
+    /// Hide automation tells (`navigator.webdriver`, headless user agent, missing plugins, WebGL
+    /// vendor) in browser sessions.
+    pub stealth: bool,
+}
+
+impl Default for TikTokApiConfig {
+    fn default() -> Self {
+        Self {
+            num_sessions: 5,
+            headless: true,
+            ms_tokens: None,
+            proxies: None,
+            proxy_pool: ProxyPoolConfig::default(),
+            base_url: "https://www.tiktok.com".to_string(),
+            browser_args: None,
+            launch: LaunchOptions::default(),
+            connect: None,
+            transport: TransportKind::Browser,
+            signer: SignerKind::Page,
+            warmup_timeout: Duration::from_secs(30),
+            health_check_interval: Some(Duration::from_secs(60)),
+            max_session_failures: 3,
+            retry: RetryPolicy::default(),
+            rate_limits: RateLimitConfig::default(),
+            captcha_handler: None,
+            fingerprints: None,
+            stealth: true,
+        }
+    }
+}
+
+/// How session browsers are started.
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    /// The Chrome or Chromium binary; detected on the system when unset.
+    pub executable: Option<PathBuf>,
+    /// Parent directory for persistent profiles, one `session-<n>` directory per session slot.
+    /// Sessions respawned by the supervisor start from a fresh profile.
+    pub user_data_dir: Option<PathBuf>,
+    /// Defaults to the screen size of the session's fingerprint.
+    pub window_size: Option<(u32, u32)>,
+    /// Turn off for containers that cannot run Chrome's sandbox (`--no-sandbox`).
+    pub sandbox: bool,
+    pub launch_timeout: Duration,
+    /// How many sessions share one Chrome process. Above 1, every session gets its own browser
+    /// context (separate cookies and proxy) and persistent profiles are not used.
+    pub sessions_per_browser: usize,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            executable: None,
+            user_data_dir: None,
+            window_size: None,
+            sandbox: true,
+            launch_timeout: Duration::from_secs(20),
+            sessions_per_browser: 1,
+        }
+    }
+}
+
+impl TikTokApiConfig {
+    pub fn builder() -> TikTokApiConfigBuilder {
+        TikTokApiConfigBuilder::default()
+    }
+
+    /// Reads the `TIKTOK_*` environment variables over the defaults.
+    ///
+    /// Lists (`TIKTOK_MS_TOKENS`, `TIKTOK_PROXIES`, `TIKTOK_CONNECT`) are comma separated, `TIKTOK_BROWSER_ARGS`
+    /// is whitespace separated, `TIKTOK_WINDOW_SIZE` looks like `1280x720` and rates look like
+    /// `10/s`, `30/m` or `500/h`. Per-endpoint rates are `TIKTOK_RATE_LIMIT_<CLASS>`, e.g.
+    /// `TIKTOK_RATE_LIMIT_SEARCH`, and `TIKTOK_HEALTH_CHECK_INTERVAL_SECS=0` turns health checks off.
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Loads a TOML or JSON file, picked by extension, over the defaults.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| config_error(format!("cannot read {}: {}", path.display(), e)))?;
+
+        let file: ConfigFile = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|e| config_error(e.to_string()))?,
+            Some("json") => serde_json::from_str(&contents).map_err(|e| config_error(e.to_string()))?,
+            _ => return Err(config_error(format!("{} is neither .toml nor .json", path.display()))),
+        };
+
+        let mut config = Self::default();
+        file.apply(&mut config)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.num_sessions == 0 {
+            return Err(config_error("num_sessions must be greater than 0"));
+        }
+        for proxy in self.proxies.iter().flatten() {
+            ProxyConfig::parse(proxy)?;
+        }
+        if self.proxy_pool.rotate_after == 0 {
+            return Err(config_error("proxy_pool.rotate_after must be greater than 0"));
+        }
+        for endpoint in self.connect.iter().flatten() {
+            validate_endpoint(endpoint)?;
+        }
+        url::Url::parse(&self.base_url)
+            .map_err(|e| config_error(format!("invalid base_url {:?}: {}", self.base_url, e)))?;
+        if let Some(executable) = &self.launch.executable {
+            if !executable.is_file() {
+                return Err(config_error(format!("Chrome executable {} does not exist", executable.display())));
+            }
+        }
+        if matches!(self.launch.window_size, Some((0, _)) | Some((_, 0))) {
+            return Err(config_error("window size must not be zero"));
+        }
+        if self.launch.sessions_per_browser == 0 {
+            return Err(config_error("sessions_per_browser must be greater than 0"));
+        }
+        let limits = &self.rate_limits;
+        let mut rates = limits.global.iter()
+            .chain(&limits.per_session)
+            .chain(&limits.per_proxy)
+            .chain(limits.per_endpoint.values());
+        if rates.any(|rate| rate.requests == 0 || rate.per.is_zero()) {
+            return Err(config_error("rate limits must allow at least 1 request per non-zero period"));
+        }
+        if !(0.0..=1.0).contains(&self.retry.jitter) {
+            return Err(config_error("retry.jitter must be between 0.0 and 1.0"));
+        }
+        Ok(())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut config = Self::default();
+        let var = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+        let list = |value: String| value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect();
+
+        if let Some(value) = var("TIKTOK_NUM_SESSIONS") {
+            config.num_sessions = parse_var("TIKTOK_NUM_SESSIONS", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_HEADLESS") {
+            config.headless = parse_bool("TIKTOK_HEADLESS", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_MS_TOKENS").or_else(|| var("TIKTOK_MS_TOKEN")) {
+            config.ms_tokens = Some(list(value));
+        }
+        if let Some(value) = var("TIKTOK_PROXIES") {
+            config.proxies = Some(list(value));
+        }
+        if let Some(value) = var("TIKTOK_PROXY_COOLDOWN_SECS") {
+            config.proxy_pool.ban_cooldown = Duration::from_secs(parse_var("TIKTOK_PROXY_COOLDOWN_SECS", &value)?);
+        }
+        if let Some(value) = var("TIKTOK_PROXY_ROTATE_AFTER") {
+            config.proxy_pool.rotate_after = parse_var("TIKTOK_PROXY_ROTATE_AFTER", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_PROXY_STICKY") {
+            config.proxy_pool.sticky = parse_sticky(&value)?;
+        }
+        if let Some(value) = var("TIKTOK_BASE_URL") {
+            config.base_url = value;
+        }
+        if let Some(value) = var("TIKTOK_BROWSER_ARGS") {
+            config.browser_args = Some(value.split_whitespace().map(str::to_string).collect());
+        }
+        if let Some(value) = var("TIKTOK_CHROME_EXECUTABLE") {
+            config.launch.executable = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("TIKTOK_USER_DATA_DIR") {
+            config.launch.user_data_dir = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("TIKTOK_WINDOW_SIZE") {
+            config.launch.window_size = Some(parse_window_size(&value)?);
+        }
+        if let Some(value) = var("TIKTOK_SANDBOX") {
+            config.launch.sandbox = parse_bool("TIKTOK_SANDBOX", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_LAUNCH_TIMEOUT_SECS") {
+            config.launch.launch_timeout = Duration::from_secs(parse_var("TIKTOK_LAUNCH_TIMEOUT_SECS", &value)?);
+        }
+        if let Some(value) = var("TIKTOK_SESSIONS_PER_BROWSER") {
+            config.launch.sessions_per_browser = parse_var("TIKTOK_SESSIONS_PER_BROWSER", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_CONNECT") {
+            config.connect = Some(list(value));
+        }
+        if let Some(value) = var("TIKTOK_STEALTH") {
+            config.stealth = parse_bool("TIKTOK_STEALTH", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_TRANSPORT") {
+            config.transport = parse_transport(&value)?;
+        }
+        if let Some(value) = var("TIKTOK_SIGNER") {
+            config.signer = parse_signer(&value)?;
+        }
+        if let Some(value) = var("TIKTOK_WARMUP_TIMEOUT_SECS") {
+            config.warmup_timeout = Duration::from_secs(parse_var("TIKTOK_WARMUP_TIMEOUT_SECS", &value)?);
+        }
+        if let Some(value) = var("TIKTOK_HEALTH_CHECK_INTERVAL_SECS") {
+            let secs: u64 = parse_var("TIKTOK_HEALTH_CHECK_INTERVAL_SECS", &value)?;
+            config.health_check_interval = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if let Some(value) = var("TIKTOK_MAX_SESSION_FAILURES") {
+            config.max_session_failures = parse_var("TIKTOK_MAX_SESSION_FAILURES", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_MAX_RETRIES") {
+            config.retry.max_retries = parse_var("TIKTOK_MAX_RETRIES", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_RETRY_BASE_DELAY_MS") {
+            config.retry.base_delay = Duration::from_millis(parse_var("TIKTOK_RETRY_BASE_DELAY_MS", &value)?);
+        }
+        if let Some(value) = var("TIKTOK_RETRY_MAX_DELAY_MS") {
+            config.retry.max_delay = Duration::from_millis(parse_var("TIKTOK_RETRY_MAX_DELAY_MS", &value)?);
+        }
+        if let Some(value) = var("TIKTOK_RETRY_JITTER") {
+            config.retry.jitter = parse_var("TIKTOK_RETRY_JITTER", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_RETRY_ROTATE_SESSION") {
+            config.retry.rotate_session = parse_bool("TIKTOK_RETRY_ROTATE_SESSION", &value)?;
+        }
+        if let Some(value) = var("TIKTOK_RATE_LIMIT_GLOBAL") {
+            config.rate_limits.global = Some(parse_var("TIKTOK_RATE_LIMIT_GLOBAL", &value)?);
+        }
+        if let Some(value) = var("TIKTOK_RATE_LIMIT_PER_SESSION") {
+            config.rate_limits.per_session = Some(parse_var("TIKTOK_RATE_LIMIT_PER_SESSION", &value)?);
+        }
+        if let Some(value) = var("TIKTOK_RATE_LIMIT_PER_PROXY") {
+            config.rate_limits.per_proxy = Some(parse_var("TIKTOK_RATE_LIMIT_PER_PROXY", &value)?);
+        }
+        for (name, class) in [
+            ("TIKTOK_RATE_LIMIT_SEARCH", EndpointClass::Search),
+            ("TIKTOK_RATE_LIMIT_COMMENTS", EndpointClass::Comments),
+            ("TIKTOK_RATE_LIMIT_ITEM_LISTS", EndpointClass::ItemLists),
+            ("TIKTOK_RATE_LIMIT_DETAIL", EndpointClass::Detail),
+        ] {
+            if let Some(value) = var(name) {
+                config.rate_limits.per_endpoint.insert(class, parse_var(name, &value)?);
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Builds a validated `TikTokApiConfig`, starting from the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct TikTokApiConfigBuilder {
+    config: TikTokApiConfig,
+}
+
+impl TikTokApiConfigBuilder {
+    pub fn num_sessions(mut self, num_sessions: usize) -> Self {
+        self.config.num_sessions = num_sessions;
+        self
+    }
+
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.config.headless = headless;
+        self
+    }
+
+    pub fn ms_token(mut self, ms_token: impl Into<String>) -> Self {
+        self.config.ms_tokens.get_or_insert_with(Vec::new).push(ms_token.into());
+        self
+    }
+
+    pub fn ms_tokens<I: IntoIterator<Item = S>, S: Into<String>>(mut self, ms_tokens: I) -> Self {
+        self.config.ms_tokens = Some(ms_tokens.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.config.proxies.get_or_insert_with(Vec::new).push(proxy.into());
+        self
+    }
+
+    pub fn proxies<I: IntoIterator<Item = S>, S: Into<String>>(mut self, proxies: I) -> Self {
+        self.config.proxies = Some(proxies.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn proxy_pool(mut self, proxy_pool: ProxyPoolConfig) -> Self {
+        self.config.proxy_pool = proxy_pool;
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.config.base_url = base_url.into();
+        self
+    }
+
+    pub fn browser_arg(mut self, arg: impl Into<String>) -> Self {
+        self.config.browser_args.get_or_insert_with(Vec::new).push(arg.into());
+        self
+    }
+
+    pub fn launch(mut self, launch: LaunchOptions) -> Self {
+        self.config.launch = launch;
+        self
+    }
+
+    pub fn chrome_executable(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.launch.executable = Some(path.into());
+        self
+    }
+
+    pub fn user_data_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.launch.user_data_dir = Some(path.into());
+        self
+    }
+
+    pub fn window_size(mut self, width: u32, height: u32) -> Self {
+        self.config.launch.window_size = Some((width, height));
+        self
+    }
+
+    pub fn no_sandbox(mut self) -> Self {
+        self.config.launch.sandbox = false;
+        self
+    }
+
+    pub fn launch_timeout(mut self, timeout: Duration) -> Self {
+        self.config.launch.launch_timeout = timeout;
+        self
+    }
+
+    pub fn sessions_per_browser(mut self, sessions: usize) -> Self {
+        self.config.launch.sessions_per_browser = sessions;
+        self
+    }
+
+    /// Adds a running browser to open sessions on instead of launching Chrome.
+    pub fn connect(mut self, endpoint: impl Into<String>) -> Self {
+        self.config.connect.get_or_insert_with(Vec::new).push(endpoint.into());
+        self
+    }
+
+    pub fn transport(mut self, transport: TransportKind) -> Self {
+        self.config.transport = transport;
+        self
+    }
+
+    pub fn signer(mut self, signer: SignerKind) -> Self {
+        self.config.signer = signer;
+        self
+    }
+
+    pub fn warmup_timeout(mut self, timeout: Duration) -> Self {
+        self.config.warmup_timeout = timeout;
+        self
+    }
+
+    pub fn health_check_interval(mut self, interval: Option<Duration>) -> Self {
+        self.config.health_check_interval = interval;
+        self
+    }
+
+    pub fn max_session_failures(mut self, max_failures: u32) -> Self {
+        self.config.max_session_failures = max_failures;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.config.retry = retry;
+        self
+    }
+
+    pub fn rate_limits(mut self, rate_limits: RateLimitConfig) -> Self {
+        self.config.rate_limits = rate_limits;
+        self
+    }
+
+    pub fn captcha_handler(mut self, handler: Arc<dyn CaptchaHandler>) -> Self {
+        self.config.captcha_handler = Some(handler);
+        self
+    }
+
+    pub fn fingerprints(mut self, fingerprints: Vec<FingerprintProfile>) -> Self {
+        self.config.fingerprints = Some(fingerprints);
+        self
+    }
+
+    pub fn stealth(mut self, stealth: bool) -> Self {
+        self.config.stealth = stealth;
+        self
+    }
+
+    pub fn build(self) -> Result<TikTokApiConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// The on-disk shape of a config file. Every field is optional and overrides the default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    num_sessions: Option<usize>,
+    headless: Option<bool>,
+    ms_tokens: Option<Vec<String>>,
+    proxies: Option<Vec<String>>,
+    proxy_pool: Option<ProxyPoolFile>,
+    base_url: Option<String>,
+    browser_args: Option<Vec<String>>,
+    launch: Option<LaunchFile>,
+    connect: Option<Vec<String>>,
+    /// `browser` or `http`.
+    transport: Option<String>,
+    /// `page`, `native` or the URL of a remote signer.
+    signer: Option<String>,
+    warmup_timeout_secs: Option<u64>,
+    /// `0` turns the health checks off.
+    health_check_interval_secs: Option<u64>,
+    max_session_failures: Option<u32>,
+    retry: Option<RetryFile>,
+    rate_limits: Option<RateLimitFile>,
+    fingerprints: Option<Vec<FingerprintProfile>>,
+    stealth: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LaunchFile {
+    executable: Option<PathBuf>,
+    user_data_dir: Option<PathBuf>,
+    /// `[width, height]`
+    window_size: Option<(u32, u32)>,
+    sandbox: Option<bool>,
+    launch_timeout_secs: Option<u64>,
+    sessions_per_browser: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProxyPoolFile {
+    rotate_after: Option<u32>,
+    ban_cooldown_secs: Option<u64>,
+    /// `session` or `fingerprint`.
+    sticky: Option<ProxyStickiness>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RetryFile {
+    max_retries: Option<u32>,
+    base_delay_ms: Option<u64>,
+    max_delay_ms: Option<u64>,
+    jitter: Option<f64>,
+    rotate_session: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RateLimitFile {
+    global: Option<String>,
+    per_session: Option<String>,
+    per_proxy: Option<String>,
+    #[serde(default)]
+    per_endpoint: HashMap<EndpointClass, String>,
+}
+
+impl ConfigFile {
+    fn apply(self, config: &mut TikTokApiConfig) -> Result<()> {
+        if let Some(num_sessions) = self.num_sessions {
+            config.num_sessions = num_sessions;
+        }
+        if let Some(headless) = self.headless {
+            config.headless = headless;
+        }
+        if self.ms_tokens.is_some() {
+            config.ms_tokens = self.ms_tokens;
+        }
+        if self.proxies.is_some() {
+            config.proxies = self.proxies;
+        }
+        if let Some(proxy_pool) = self.proxy_pool {
+            if let Some(rotate_after) = proxy_pool.rotate_after {
+                config.proxy_pool.rotate_after = rotate_after;
+            }
+            if let Some(secs) = proxy_pool.ban_cooldown_secs {
+                config.proxy_pool.ban_cooldown = Duration::from_secs(secs);
+            }
+            if let Some(sticky) = proxy_pool.sticky {
+                config.proxy_pool.sticky = sticky;
+            }
+        }
+        if let Some(base_url) = self.base_url {
+            config.base_url = base_url;
+        }
+        if self.browser_args.is_some() {
+            config.browser_args = self.browser_args;
+        }
+        if let Some(launch) = self.launch {
+            if launch.executable.is_some() {
+                config.launch.executable = launch.executable;
+            }
+            if launch.user_data_dir.is_some() {
+                config.launch.user_data_dir = launch.user_data_dir;
+            }
+            if launch.window_size.is_some() {
+                config.launch.window_size = launch.window_size;
+            }
+            if let Some(sandbox) = launch.sandbox {
+                config.launch.sandbox = sandbox;
+            }
+            if let Some(secs) = launch.launch_timeout_secs {
+                config.launch.launch_timeout = Duration::from_secs(secs);
+            }
+            if let Some(sessions) = launch.sessions_per_browser {
+                config.launch.sessions_per_browser = sessions;
+            }
+        }
+        if self.connect.is_some() {
+            config.connect = self.connect;
+        }
+        if let Some(transport) = self.transport {
+            config.transport = parse_transport(&transport)?;
+        }
+        if let Some(signer) = self.signer {
+            config.signer = parse_signer(&signer)?;
+        }
+        if let Some(secs) = self.warmup_timeout_secs {
+            config.warmup_timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = self.health_check_interval_secs {
+            config.health_check_interval = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if let Some(max_failures) = self.max_session_failures {
+            config.max_session_failures = max_failures;
+        }
+        if self.fingerprints.is_some() {
+            config.fingerprints = self.fingerprints;
+        }
+        if let Some(stealth) = self.stealth {
+            config.stealth = stealth;
+        }
+
+        if let Some(retry) = self.retry {
+            let policy = &mut config.retry;
+            if let Some(max_retries) = retry.max_retries {
+                policy.max_retries = max_retries;
+            }
+            if let Some(ms) = retry.base_delay_ms {
+                policy.base_delay = Duration::from_millis(ms);
+            }
+            if let Some(ms) = retry.max_delay_ms {
+                policy.max_delay = Duration::from_millis(ms);
+            }
+            if let Some(jitter) = retry.jitter {
+                policy.jitter = jitter;
+            }
+            if let Some(rotate_session) = retry.rotate_session {
+                policy.rotate_session = rotate_session;
+            }
+        }
+
+        if let Some(rate_limits) = self.rate_limits {
+            let limits = &mut config.rate_limits;
+            if let Some(rate) = rate_limits.global {
+                limits.global = Some(parse_var("rate_limits.global", &rate)?);
+            }
+            if let Some(rate) = rate_limits.per_session {
+                limits.per_session = Some(parse_var("rate_limits.per_session", &rate)?);
+            }
+            if let Some(rate) = rate_limits.per_proxy {
+                limits.per_proxy = Some(parse_var("rate_limits.per_proxy", &rate)?);
+            }
+            for (class, rate) in rate_limits.per_endpoint {
+                limits.per_endpoint.insert(class, parse_var::<Rate>("rate_limits.per_endpoint", &rate)?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn config_error(message: impl Into<String>) -> TikTokError {
+    TikTokError::Config(message.into())
+}
+
+fn validate_endpoint(endpoint: &str) -> Result<()> {
+    let url = url::Url::parse(endpoint)
+        .map_err(|e| config_error(format!("invalid DevTools endpoint {:?}: {}", endpoint, e)))?;
+    if !DEVTOOLS_SCHEMES.contains(&url.scheme()) {
+        return Err(config_error(format!("DevTools endpoint {:?} must use one of {:?}", endpoint, DEVTOOLS_SCHEMES)));
+    }
+    Ok(())
+}
+
+fn parse_var<T>(name: &str, value: &str) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value.trim().parse().map_err(|e| config_error(format!("invalid {} {:?}: {}", name, value, e)))
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(config_error(format!("invalid {} {:?}: expected true or false", name, value))),
+    }
+}
+
+fn parse_window_size(value: &str) -> Result<(u32, u32)> {
+    value.split_once(['x', 'X'])
+        .and_then(|(width, height)| Some((width.trim().parse().ok()?, height.trim().parse().ok()?)))
+        .ok_or_else(|| config_error(format!("invalid TIKTOK_WINDOW_SIZE {:?}, expected <width>x<height>", value)))
+}
+
+fn parse_transport(value: &str) -> Result<TransportKind> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "browser" => Ok(TransportKind::Browser),
+        "http" => Ok(TransportKind::Http),
+        other => Err(config_error(format!("unknown transport {:?}, expected browser or http", other))),
+    }
+}
+
+fn parse_sticky(value: &str) -> Result<ProxyStickiness> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "session" => Ok(ProxyStickiness::Session),
+        "fingerprint" => Ok(ProxyStickiness::Fingerprint),
+        other => Err(config_error(format!("unknown proxy stickiness {:?}, expected session or fingerprint", other))),
+    }
+}
+
+fn parse_signer(value: &str) -> Result<SignerKind> {
+    let value = value.trim();
+    match value.to_ascii_lowercase().as_str() {
+        "page" => Ok(SignerKind::Page),
+        "native" => Ok(SignerKind::Native),
+        _ if value.starts_with("http://") || value.starts_with("https://") => Ok(SignerKind::Remote(value.to_string())),
+        _ => Err(config_error(format!("unknown signer {:?}, expected page, native or a URL", value))),
+    }
+}
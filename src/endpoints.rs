@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use url::Url;
+
+use crate::TikTokApi;
+
+/// Web API endpoints, resolved against `TikTokApiConfig::base_url`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    UserDetail,
+    UserPosts,
+    UserLiked,
+    ItemDetail,
+    RelatedItems,
+    CommentList,
+    CommentReplies,
+    CommentDetail,
+    MusicDetail,
+    MusicItems,
+    ChallengeDetail,
+    ChallengeItems,
+    Recommend,
+    SearchUser,
+    SearchVideo,
+}
+
+/// Groups of endpoints that share a rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointClass {
+    Search,
+    Comments,
+    ItemLists,
+    Detail,
+}
+
+impl Endpoint {
+    pub const ALL: [Endpoint; 15] = [
+        Endpoint::UserDetail,
+        Endpoint::UserPosts,
+        Endpoint::UserLiked,
+        Endpoint::ItemDetail,
+        Endpoint::RelatedItems,
+        Endpoint::CommentList,
+        Endpoint::CommentReplies,
+        Endpoint::CommentDetail,
+        Endpoint::MusicDetail,
+        Endpoint::MusicItems,
+        Endpoint::ChallengeDetail,
+        Endpoint::ChallengeItems,
+        Endpoint::Recommend,
+        Endpoint::SearchUser,
+        Endpoint::SearchVideo,
+    ];
+
+    pub fn class(&self) -> EndpointClass {
+        match self {
+            Endpoint::SearchUser | Endpoint::SearchVideo => EndpointClass::Search,
+            Endpoint::CommentList | Endpoint::CommentReplies | Endpoint::CommentDetail => EndpointClass::Comments,
+            Endpoint::UserPosts
+            | Endpoint::UserLiked
+            | Endpoint::RelatedItems
+            | Endpoint::MusicItems
+            | Endpoint::ChallengeItems
+            | Endpoint::Recommend => EndpointClass::ItemLists,
+            Endpoint::UserDetail
+            | Endpoint::ItemDetail
+            | Endpoint::MusicDetail
+            | Endpoint::ChallengeDetail => EndpointClass::Detail,
+        }
+    }
+
+    /// The endpoint a request URL points at, whatever host and base path it is served from.
+    pub fn from_url(url: &str) -> Option<Endpoint> {
+        let path = Url::parse(url).ok()?.path().to_string();
+        Endpoint::ALL.into_iter().find(|endpoint| path.ends_with(endpoint.path()))
+    }
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            Endpoint::UserDetail => "/api/user/detail/",
+            Endpoint::UserPosts => "/api/post/item_list/",
+            Endpoint::UserLiked => "/api/favorite/item_list",
+            Endpoint::ItemDetail => "/api/item/detail/",
+            Endpoint::RelatedItems => "/api/related/item_list/",
+            Endpoint::CommentList => "/api/comment/list/",
+            Endpoint::CommentReplies => "/api/comment/list/reply/",
+            Endpoint::CommentDetail => "/api/comment/detail/",
+            Endpoint::MusicDetail => "/api/music/detail/",
+            Endpoint::MusicItems => "/api/music/item_list/",
+            Endpoint::ChallengeDetail => "/api/challenge/detail/",
+            Endpoint::ChallengeItems => "/api/challenge/item_list/",
+            Endpoint::Recommend => "/api/recommend/item_list/",
+            Endpoint::SearchUser => "/api/search/user/full/",
+            Endpoint::SearchVideo => "/api/search/video/full/",
+        }
+    }
+}
+
+/// Joins `path` onto `base_url`, tolerating a trailing slash on the base.
+pub fn resolve(base_url: &str, path: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+}
+
+impl TikTokApi {
+    pub fn endpoint_url(&self, endpoint: Endpoint) -> String {
+        resolve(&self.config.base_url, endpoint.path())
+    }
+}
@@ -1,5 +1,36 @@
+use std::fmt;
 use thiserror::Error;
 
+/// The `status_code` TikTok answered with, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiStatus {
+    pub code: i64,
+    pub message: Option<String>,
+    /// Path of the endpoint that answered, e.g. `/api/user/detail/`.
+    pub endpoint: String,
+    pub log_id: Option<String>,
+}
+
+impl ApiStatus {
+    /// An answer from `endpoint` without an error code that still lacked the requested object.
+    pub(crate) fn missing(endpoint: &str) -> Self {
+        Self { code: 0, message: None, endpoint: endpoint.to_string(), log_id: None }
+    }
+}
+
+impl fmt::Display for ApiStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "status code {} from {}", self.code, self.endpoint)?;
+        if let Some(message) = &self.message {
+            write!(f, " ({})", message)?;
+        }
+        if let Some(log_id) = &self.log_id {
+            write!(f, ", log id {}", log_id)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum TikTokError {
     #[error("TikTok returned invalid JSON response")]
@@ -11,11 +42,37 @@ pub enum TikTokError {
     #[error("TikTok API error: {0}")]
     ApiError(String),
 
-    #[error("TikTok captcha required")]
-    CaptchaRequired,
+    #[error("TikTok answered {url} with HTTP {status}")]
+    HttpStatus { status: u16, url: String },
+
+    #[error("TikTok captcha required on session {session_index} for {url}")]
+    CaptchaRequired {
+        session_index: usize,
+        url: String,
+        subtype: Option<String>,
+    },
+
+    /// The user, video, comment, sound or hashtag does not exist.
+    #[error("Object not found: {0}")]
+    NotFound(ApiStatus),
+
+    #[error("Account is private: {0}")]
+    PrivateAccount(ApiStatus),
+
+    #[error("Video was deleted or is under review: {0}")]
+    VideoUnavailable(ApiStatus),
+
+    #[error("Content is not available in this region: {0}")]
+    RegionRestricted(ApiStatus),
+
+    #[error("Throttled by TikTok: {0}")]
+    RateLimited(ApiStatus),
 
-    #[error("Object not found")]
-    NotFound,
+    #[error("Login required: {0}")]
+    LoginRequired(ApiStatus),
+
+    #[error("Invalid request parameters: {0}")]
+    InvalidParameters(ApiStatus),
 
     #[error("Sound was removed by TikTok")]
     SoundRemoved,
@@ -32,8 +89,114 @@ pub enum TikTokError {
     #[error("Invalid URL format: {0}")]
     InvalidUrl(String),
 
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    /// Sessions that did not shut down cleanly, by index; the other sessions were still closed.
+    #[error("Failed to close {}", describe_close_errors(.0))]
+    CloseFailed(Vec<(usize, TikTokError)>),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
-pub type Result<T> = std::result::Result<T, TikTokError>;
\ No newline at end of file
+impl TikTokError {
+    /// Errors that point at a broken session rather than a bad request.
+    pub fn is_session_failure(&self) -> bool {
+        matches!(
+            self,
+            TikTokError::BrowserError(_)
+                | TikTokError::RequestError(_)
+                | TikTokError::EmptyResponse
+                | TikTokError::InvalidJSON(_)
+                | TikTokError::CaptchaRequired { .. }
+                | TikTokError::HttpStatus { status: 403 | 429, .. }
+                | TikTokError::RateLimited(_)
+        )
+    }
+
+    /// Errors that mean TikTok is pushing back on the IP, so its proxy should cool down.
+    pub fn is_ban(&self) -> bool {
+        matches!(
+            self,
+            TikTokError::CaptchaRequired { .. }
+                | TikTokError::HttpStatus { status: 403 | 429, .. }
+                | TikTokError::RateLimited(_)
+        )
+    }
+
+    /// Maps a non-zero TikTok `status_code` to its error.
+    pub(crate) fn from_api_status(status: ApiStatus, session_index: usize, url: &str) -> Self {
+        match status.code {
+            10000 => TikTokError::CaptchaRequired { session_index, url: url.to_string(), subtype: None },
+            10202 | 10203 | 10205 | 10221 => TikTokError::NotFound(status),
+            10216 | 10222 => TikTokError::PrivateAccount(status),
+            10204 | 10215 | 10217 | 10227 | 10228 => TikTokError::VideoUnavailable(status),
+            10213 | 10231 => TikTokError::RegionRestricted(status),
+            10113 | 10114 | 10404 => TikTokError::RateLimited(status),
+            8 | 10102 | 10119 => TikTokError::LoginRequired(status),
+            5 | 10201 => TikTokError::InvalidParameters(status),
+            _ => TikTokError::ApiError(status.to_string()),
+        }
+    }
+
+    /// The TikTok status behind this error, for the typed status variants.
+    pub fn api_status(&self) -> Option<&ApiStatus> {
+        match self {
+            TikTokError::NotFound(status)
+            | TikTokError::PrivateAccount(status)
+            | TikTokError::VideoUnavailable(status)
+            | TikTokError::RegionRestricted(status)
+            | TikTokError::RateLimited(status)
+            | TikTokError::LoginRequired(status)
+            | TikTokError::InvalidParameters(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status behind this error, when there was a response.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            TikTokError::HttpStatus { status, .. } => Some(*status),
+            TikTokError::RequestError(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Whether a request that failed with this error may succeed if sent again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TikTokError::EmptyResponse
+            | TikTokError::InvalidJSON(_)
+            | TikTokError::ApiError(_)
+            | TikTokError::RateLimited(_)
+            | TikTokError::BrowserError(_)
+            | TikTokError::SignatureError => true,
+            TikTokError::RequestError(e) => e.status().is_none_or(|s| {
+                s.is_server_error() || s == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }),
+            TikTokError::HttpStatus { status, .. } => *status >= 500 || *status == 429,
+            TikTokError::CaptchaRequired { .. }
+            | TikTokError::NotFound(_)
+            | TikTokError::PrivateAccount(_)
+            | TikTokError::VideoUnavailable(_)
+            | TikTokError::RegionRestricted(_)
+            | TikTokError::LoginRequired(_)
+            | TikTokError::InvalidParameters(_)
+            | TikTokError::SoundRemoved
+            | TikTokError::InvalidUrl(_)
+            | TikTokError::Config(_)
+            | TikTokError::CloseFailed(_)
+            | TikTokError::Other(_) => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, TikTokError>;
+
+fn describe_close_errors(errors: &[(usize, TikTokError)]) -> String {
+    errors.iter()
+        .map(|(index, error)| format!("session {}: {}", index, error))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -0,0 +1,113 @@
+use chromiumoxide::Page;
+use chromiumoxide::cdp::browser_protocol::emulation::{
+    SetDeviceMetricsOverrideParams, SetLocaleOverrideParams, SetTimezoneOverrideParams, UserAgentMetadata,
+};
+use chromiumoxide::cdp::browser_protocol::network::SetUserAgentOverrideParams;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    types::SessionConfig,
+};
+
+/// A consistent set of browser traits a session presents to TikTok.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FingerprintProfile {
+    pub user_agent: String,
+    /// `navigator.platform`, e.g. `Win32` or `MacIntel`.
+    pub platform: String,
+    pub language: String,
+    pub timezone: String,
+    pub screen_width: u32,
+    pub screen_height: u32,
+}
+
+impl FingerprintProfile {
+    pub fn builtin() -> Vec<FingerprintProfile> {
+        let profile = |user_agent: &str, platform: &str, language: &str, timezone: &str, width, height| {
+            FingerprintProfile {
+                user_agent: user_agent.to_string(),
+                platform: platform.to_string(),
+                language: language.to_string(),
+                timezone: timezone.to_string(),
+                screen_width: width,
+                screen_height: height,
+            }
+        };
+
+        vec![
+            profile(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+                "Win32", "en-US", "America/New_York", 1920, 1080,
+            ),
+            profile(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Safari/537.36",
+                "Win32", "en-US", "America/Chicago", 1366, 768,
+            ),
+            profile(
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+                "MacIntel", "en-US", "America/Los_Angeles", 1440, 900,
+            ),
+            profile(
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Safari/537.36",
+                "MacIntel", "en-GB", "Europe/London", 1680, 1050,
+            ),
+            profile(
+                "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+                "Linux x86_64", "en-US", "America/Denver", 1920, 1080,
+            ),
+        ]
+    }
+
+    /// Picks one of `profiles`, or of the built-in set when none are configured.
+    pub fn choose(profiles: Option<&[FingerprintProfile]>) -> FingerprintProfile {
+        let builtin;
+        let profiles = match profiles {
+            Some(profiles) if !profiles.is_empty() => profiles,
+            _ => {
+                builtin = Self::builtin();
+                &builtin
+            }
+        };
+        profiles.choose(&mut rand::thread_rng()).cloned().unwrap()
+    }
+
+    pub fn apply_to(&self, session: &mut SessionConfig) {
+        session.user_agent = Some(self.user_agent.clone());
+        session.platform = self.platform.clone();
+        session.language = self.language.clone();
+        session.timezone = self.timezone.clone();
+        session.screen_width = self.screen_width;
+        session.screen_height = self.screen_height;
+    }
+}
+
+/// Applies a session's fingerprint to its page through CDP emulation, with `metadata` as the
+/// client hints of its user agent.
+pub(crate) async fn emulate(page: &Page, session: &SessionConfig, metadata: Option<UserAgentMetadata>) -> Result<()> {
+    if let Some(user_agent) = &session.user_agent {
+        let mut params = SetUserAgentOverrideParams::builder()
+            .user_agent(user_agent.clone())
+            .accept_language(session.language.clone())
+            .platform(session.platform.clone())
+            .build()?;
+        params.user_agent_metadata = metadata;
+        page.execute(params).await?;
+    }
+
+    page.execute(SetTimezoneOverrideParams::new(session.timezone.clone())).await?;
+    page.execute(SetLocaleOverrideParams::builder().locale(session.language.clone()).build()).await?;
+
+    let mut metrics = SetDeviceMetricsOverrideParams::new(
+        session.screen_width as i64,
+        session.screen_height as i64,
+        1.0,
+        false,
+    );
+    metrics.screen_width = Some(session.screen_width as i64);
+    metrics.screen_height = Some(session.screen_height as i64);
+    page.execute(metrics).await?;
+
+    Ok(())
+}
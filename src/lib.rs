@@ -1,17 +1,41 @@
 pub mod api;
+mod browser;
+pub mod captcha;
+pub mod config;
+pub mod endpoints;
 pub mod error;
+pub mod fingerprint;
+mod persist;
+pub mod proxy;
+pub mod rate_limit;
+pub mod request;
+pub mod retry;
+pub mod session;
+pub mod signer;
+pub mod stealth;
+pub mod supervisor;
+pub mod transport;
 pub mod types;
 
-use chromiumoxide::{Browser, BrowserConfig};
-use chromiumoxide::cdp::browser_protocol::target::CreateTargetParams;
-use futures::StreamExt;
-use rand::{Rng, seq::SliceRandom};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Instant;
+use reqwest::Method;
+use tokio::task::JoinHandle;
 use url::Url;
 use std::collections::HashMap;
+pub use config::{LaunchOptions, TikTokApiConfig, TikTokApiConfigBuilder};
+
 use crate::{
+    captcha::{CaptchaChallenge, CaptchaResolution},
+    endpoints::Endpoint,
     error::{Result, TikTokError},
+    proxy::{ProxyConfig, ProxyOutcome, ProxyPool, ProxyStats},
+    rate_limit::{RateLimitStats, RateLimiter},
+    request::{ApiRequest, RawResponse, RequestBody, RequestBuilder},
+    session::{SessionFactory, SessionLease, SessionPool},
+    signer::{SignRequest, Signer},
+    supervisor::{SessionEvent, SessionEventKind, Supervisor},
+    transport::{Transport, TransportRequest, DEFAULT_USER_AGENT},
     types::{SessionConfig, TikTokSession, RequestParams},
 };
 
@@ -33,98 +57,107 @@ impl From<String> for TikTokError {
     }
 }
 
+const MAX_CAPTCHA_ROUNDS: u32 = 3;
+
 pub struct TikTokApi {
-    sessions: Arc<Mutex<Vec<TikTokSession>>>,
+    sessions: Arc<SessionPool>,
+    proxies: Arc<ProxyPool>,
+    factory: Arc<SessionFactory>,
+    supervisor: Arc<Supervisor>,
+    supervisor_task: Option<JoinHandle<()>>,
+    rate_limiter: RateLimiter,
+    transport: Arc<dyn Transport>,
+    signer: Arc<dyn Signer>,
     config: TikTokApiConfig,
 }
 
-#[derive(Debug, Clone)]
-pub struct TikTokApiConfig {
-    pub num_sessions: usize,
-    pub headless: bool,
-    pub ms_tokens: Option<Vec<String>>,
-    pub proxies: Option<Vec<String>>,
-    pub base_url: String,
-    pub browser_args: Option<Vec<String>>,
-}
-
-impl Default for TikTokApiConfig {
-    fn default() -> Self {
-        Self {
-            num_sessions: 5,
-            headless: true,
-            ms_tokens: None,
-            proxies: None,
-            base_url: "https://www.tiktok.com".to_string(),
-            browser_args: None,
-        }
-    }
-}
-
 impl TikTokApi {
     pub async fn new(config: TikTokApiConfig) -> Result<Self> {
-        let sessions = Arc::new(Mutex::new(Vec::new()));
-        let api = Self { sessions, config };
-        api.create_sessions().await?;
+        Self::with_saved_sessions(config, Vec::new()).await
+    }
+
+    async fn with_saved_sessions(config: TikTokApiConfig, saved: Vec<SessionConfig>) -> Result<Self> {
+        config.validate()?;
+        let sessions = Arc::new(SessionPool::default());
+        let transport = config.transport.build();
+        let signer = config.signer.build();
+        let proxies = Arc::new(ProxyPool::new(config.proxies.clone().unwrap_or_default(), config.proxy_pool.clone()));
+        let factory = Arc::new(SessionFactory::new(config.clone(), transport.needs_browser(), proxies.clone()));
+        let supervisor = Arc::new(Supervisor::new(sessions.clone(), factory.clone()));
+
+        let mut api = Self {
+            sessions,
+            proxies,
+            factory,
+            supervisor,
+            supervisor_task: None,
+            rate_limiter: RateLimiter::new(config.rate_limits.clone()),
+            transport,
+            signer,
+            config,
+        };
+        api.restore_sessions(saved).await?;
+
+        if let Some(interval) = api.config.health_check_interval {
+            api.supervisor_task = Some(api.supervisor.spawn(interval));
+        }
         Ok(api)
     }
 
     pub async fn create_sessions(&self) -> Result<()> {
-        let mut sessions = self.sessions.lock().await;
+        self.restore_sessions(Vec::new()).await
+    }
 
+    /// Creates `num_sessions` sessions, the first ones from `saved` state.
+    async fn restore_sessions(&self, saved: Vec<SessionConfig>) -> Result<()> {
+        let mut saved = saved.into_iter();
         for _ in 0..self.config.num_sessions {
-            let proxy = self.config.proxies
-                .as_ref()
-                .and_then(|proxies| proxies.choose(&mut rand::thread_rng()))
-                .cloned();
-
-            let mut config_builder = BrowserConfig::builder();
-            if !self.config.headless {
-                config_builder = config_builder.with_head();
-            }
-            if let Some(proxy_str) = proxy.as_deref() {
-                config_builder = config_builder.arg("--proxy-server=".to_string() + proxy_str);
-            }
-            let browser_config = config_builder.build()?;
-
-            let (browser, mut handler) = Browser::launch(browser_config).await?;
-            
-            // Handle browser events in background
-            tokio::spawn(async move {
-                while let Some(event) = handler.next().await {
-                    log::debug!("Browser event: {:?}", event);
-                }
-            });
+            let session = self.factory.create(self.sessions.len(), saved.next()).await?;
+            // Only warmed-up sessions enter the pool
+            self.sessions.push(session);
+        }
 
-            let page = browser.new_page(CreateTargetParams::default()).await?;
-
-            let ms_token = self.config.ms_tokens
-                .as_ref()
-                .and_then(|tokens| tokens.choose(&mut rand::thread_rng()))
-                .cloned();
-
-            let config = SessionConfig {
-                user_agent: None, // Will be set after page load
-                language: "en-US".to_string(),
-                platform: "Windows".to_string(),
-                timezone: "America/New_York".to_string(),
-                screen_width: 1920,
-                screen_height: 1080,
-                ms_token,
-                proxy,
-                cookies: None,
-            };
+        Ok(())
+    }
 
-            let session = TikTokSession {
-                config,
-                browser: browser.into(),
-                page: page.into(),
-            };
+    /// Probes idle sessions now and respawns any that are dead or quarantined.
+    pub async fn check_sessions(&self) -> Result<()> {
+        self.supervisor.check().await
+    }
+
+    pub fn session_events(&self) -> Vec<SessionEvent> {
+        self.supervisor.events()
+    }
+
+    pub fn rate_limit_stats(&self) -> RateLimitStats {
+        self.rate_limiter.stats()
+    }
+
+    pub fn proxy_stats(&self) -> Vec<ProxyStats> {
+        self.proxies.stats()
+    }
 
-            sessions.push(session);
+    /// Makes a proxy available to sessions created or respawned from now on.
+    pub fn add_proxy(&self, proxy: &str) -> Result<()> {
+        self.proxies.add(proxy)
+    }
+
+    /// Stops using a proxy. Sessions on it are taken out of rotation and moved to another proxy
+    /// by the next health check. Returns whether the proxy was in the pool.
+    pub fn remove_proxy(&self, proxy: &str) -> bool {
+        let removed = self.proxies.remove(proxy);
+        for (index, session) in self.sessions.snapshot().iter().enumerate() {
+            if session.config.proxy.as_deref() == Some(proxy) {
+                self.sessions.quarantine(index);
+                self.supervisor.record(index, SessionEventKind::Quarantined { reason: "proxy removed".to_string() });
+            }
         }
+        removed
+    }
 
-        Ok(())
+    /// Starts a request with any method, e.g. a POST with a JSON body.
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder<'_> {
+        RequestBuilder::new(self, method, url)
     }
 
     pub async fn make_request(
@@ -134,111 +167,295 @@ impl TikTokApi {
         headers: Option<HashMap<String, String>>,
         session_index: Option<usize>,
     ) -> Result<serde_json::Value> {
-        let sessions = self.sessions.lock().await;
-        let session_idx = session_index.unwrap_or_else(|| {
-            rand::thread_rng().gen_range(0..sessions.len())
-        });
-        
-        let session = sessions.get(session_idx)
-            .ok_or_else(|| TikTokError::Other(anyhow::anyhow!("Invalid session index")))?;
-
-        let signed_url = self.sign_url(url, &params).await?;
-        
-        // Prepare fetch request script
-        let fetch_script = format!(
-            r#"
-            async () => {{
-                const response = await fetch("{}", {{
-                    method: "GET",
-                    headers: {},
-                }});
-                return await response.text();
-            }}
-            "#,
-            signed_url,
-            serde_json::to_string(&headers.unwrap_or_default())?
-        );
-
-        let result = session.page.evaluate(fetch_script).await?;
-        let response_text = result.value().and_then(|v| v.as_str())
-            .ok_or_else(|| TikTokError::EmptyResponse)?;
-
-        if response_text.is_empty() {
-            return Err(TikTokError::EmptyResponse);
-        }
-
-        let json: serde_json::Value = serde_json::from_str(response_text)?;
-        
-        if let Some(status_code) = json.get("status_code") {
-            if status_code != 0 {
-                return Err(TikTokError::ApiError(
-                    format!("TikTok API error: Status code {}", status_code)
-                ));
-            }
+        let request = ApiRequest::get(url, params, headers, session_index);
+        self.execute(&request, |response| response.json()).await
+    }
+
+    /// Like `make_request`, but returns the status, headers and timing alongside the unparsed body.
+    pub async fn make_raw_request(
+        &self,
+        url: &str,
+        params: Option<RequestParams>,
+        headers: Option<HashMap<String, String>>,
+        session_index: Option<usize>,
+    ) -> Result<RawResponse> {
+        let request = ApiRequest::get(url, params, headers, session_index);
+        self.execute(&request, Ok).await
+    }
+
+    /// Fetches a media URL outside the browser, through a session's proxy and with its user agent.
+    /// The session stays free for API requests meanwhile, but the download counts against its
+    /// rate limits and proxy health.
+    pub(crate) async fn download(&self, url: &str, headers: reqwest::header::HeaderMap) -> Result<Vec<u8>> {
+        self.rate_limiter.acquire_shared(None).await;
+        let (index, session) = self.sessions.pick()?;
+        let proxy = session.config.proxy.as_deref();
+        self.rate_limiter.acquire_session(index, proxy).await;
+
+        let started = Instant::now();
+        let result = self.fetch_media(&session, url, headers).await;
+        self.record_proxy(index, proxy, &result, started.elapsed());
+        result
+    }
+
+    async fn fetch_media(&self, session: &TikTokSession, url: &str, headers: reqwest::header::HeaderMap) -> Result<Vec<u8>> {
+        let mut builder = reqwest::Client::builder().default_headers(headers);
+        if let Some(proxy) = session.config.proxy.as_deref() {
+            builder = builder.proxy(ProxyConfig::parse(proxy)?.to_reqwest()?);
         }
+        let client = builder.build()?;
 
-        Ok(json)
+        let user_agent = session.config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let response = client
+            .get(url)
+            .header(reqwest::header::USER_AGENT, user_agent)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(TikTokError::HttpStatus { status: response.status().as_u16(), url: url.to_string() });
+        }
+
+        Ok(response.bytes().await?.to_vec())
     }
 
-    async fn sign_url(&self, url: &str, params: &Option<RequestParams>) -> Result<String> {
-        let sessions = self.sessions.lock().await;
-        let session = sessions.get(0)
-            .ok_or_else(|| TikTokError::Other(anyhow::anyhow!("No sessions available")))?;
+    /// Sends `request` with retries, session rotation and captcha handling; `decode` runs on every
+    /// response so its errors are retried like transport errors.
+    pub(crate) async fn execute<T>(
+        &self,
+        request: &ApiRequest,
+        decode: impl Fn(RawResponse) -> Result<T>,
+    ) -> Result<T> {
+        let url = request.url.as_str();
+        let session_index = request.session_index;
+        let policy = &self.config.retry;
+        let mut attempt = 0;
+        let mut previous = None;
+        let endpoint_class = Endpoint::from_url(url).map(|endpoint| endpoint.class());
+
+        loop {
+            self.rate_limiter.acquire_shared(endpoint_class).await;
+            let session = match (session_index, previous) {
+                (None, Some(previous)) if policy.rotate_session => self.sessions.lease_avoiding(previous).await?,
+                _ => self.sessions.lease(session_index).await?,
+            };
+            self.rate_limiter.acquire_session(session.index(), session.config.proxy.as_deref()).await;
+
+            let mut started = Instant::now();
+            let mut result = self.send_on(&session, request).await.and_then(&decode);
 
-        // Convert params to URL query string if provided
-        let mut final_url = if let Some(params) = params {
-            let mut url = Url::parse(url)?;
-            let param_map = serde_json::to_value(params)?;
-            
-            if let serde_json::Value::Object(map) = param_map {
-                for (key, value) in map {
-                    url.query_pairs_mut().append_pair(&key, &value.to_string());
+            // Give the captcha handler a chance to clear the challenge before giving up
+            let mut captcha_rounds = 0;
+            let mut rotate = false;
+            while let (Err(TikTokError::CaptchaRequired { url: challenge_url, subtype, .. }), Some(handler)) =
+                (&result, &self.config.captcha_handler)
+            {
+                if captcha_rounds >= MAX_CAPTCHA_ROUNDS {
+                    break;
+                }
+                captcha_rounds += 1;
+
+                let challenge = CaptchaChallenge {
+                    session_index: session.index(),
+                    url: challenge_url,
+                    subtype: subtype.as_deref(),
+                    session: &session,
+                };
+                match handler.handle(&challenge).await? {
+                    CaptchaResolution::Solved => {
+                        started = Instant::now();
+                        result = self.send_on(&session, request).await.and_then(&decode);
+                    }
+                    CaptchaResolution::RotateSession => {
+                        rotate = true;
+                        break;
+                    }
+                    CaptchaResolution::GiveUp => break,
                 }
             }
-            url.to_string()
-        } else {
-            url.to_string()
+
+            // Only a captcha left unsolved counts against the proxy
+            self.record_proxy(session.index(), session.config.proxy.as_deref(), &result, started.elapsed());
+
+            if rotate && session_index.is_none() {
+                session.quarantine();
+                self.supervisor.record(session.index(), SessionEventKind::Quarantined { reason: "captcha".to_string() });
+                previous = Some(session.index());
+                continue;
+            }
+
+            match &result {
+                Ok(_) => session.record_success(),
+                Err(e) if e.is_session_failure() => {
+                    if session.record_failure(self.config.max_session_failures) {
+                        self.supervisor.record(session.index(), SessionEventKind::Quarantined { reason: e.to_string() });
+                    }
+                }
+                Err(_) => {}
+            }
+
+            match result {
+                Err(e) if e.is_retryable() && attempt < policy.max_retries => {
+                    attempt += 1;
+                    previous = Some(session.index());
+                    drop(session);
+
+                    let delay = policy.delay(attempt);
+                    log::debug!("Request to {} failed ({}), retry {} in {:?}", url, e, attempt, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Feeds the outcome into the proxy's health and moves the session off a proxy that went bad.
+    fn record_proxy<T>(&self, session_index: usize, proxy: Option<&str>, result: &Result<T>, elapsed: std::time::Duration) {
+        let Some(proxy) = proxy else {
+            return;
         };
+        let outcome = match result {
+            Err(e) if e.is_ban() => ProxyOutcome::Banned,
+            Err(e) if e.is_session_failure() => ProxyOutcome::Failure,
+            // TikTok answered, so the proxy did its job
+            _ => ProxyOutcome::Success(elapsed),
+        };
+        if self.proxies.record(proxy, outcome) {
+            self.sessions.quarantine(session_index);
+            self.supervisor.record(session_index, SessionEventKind::Quarantined {
+                reason: format!("proxy {} needs rotating", proxy),
+            });
+        }
+    }
 
-        // Generate X-Bogus
-        let script = format!(
-            r#"
-            async () => {{
-                if (typeof window.byted_acrawler === 'undefined') {{
-                    return null;
-                }}
-                return window.byted_acrawler.frontierSign("{}");
-            }}
-            "#,
-            final_url
-        );
-
-        let result = session.page.evaluate(script).await?;
-        
-        let x_bogus = result.value()
-            .and_then(|v| v.as_str())
-            .and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok())
-            .and_then(|v| v.get("X-Bogus").and_then(|b| b.as_str()).map(|s| s.to_string()))
-            .ok_or(TikTokError::SignatureError)?;
-
-        // Append X-Bogus to URL
-        if final_url.contains('?') {
-            final_url.push('&');
-        } else {
-            final_url.push('?');
+    async fn send_on(&self, session: &SessionLease, request: &ApiRequest) -> Result<RawResponse> {
+        let body = request.body.as_ref();
+        let signed_url = self.sign_url(session, &request.url, &request.params, body).await?;
+
+        let mut headers = request.headers.clone();
+        if let Some(body) = body {
+            if !headers.keys().any(|name| name.eq_ignore_ascii_case("content-type")) {
+                headers.insert("content-type".to_string(), body.content_type.clone());
+            }
+        }
+        let request = TransportRequest {
+            url: signed_url,
+            method: request.method.clone(),
+            headers,
+            body: body.map(|body| body.data.clone()),
+        };
+
+        let started = Instant::now();
+        let response = self.transport.send_raw(session, &request).await?;
+        let response = RawResponse {
+            status: response.status,
+            headers: response.headers,
+            url: response.url,
+            elapsed: started.elapsed(),
+            session_index: session.index(),
+            body: response.body,
+        };
+
+        let captcha = captcha::detect(&response.body, session.index(), &request.url)
+            .or_else(|| captcha::detect_redirect(&response.url, session.index(), &request.url));
+        if let Some(captcha) = captcha {
+            return Err(captcha);
         }
-        final_url.push_str(&format!("X-Bogus={}", x_bogus));
 
-        Ok(final_url)
+        if !response.is_success() {
+            return Err(TikTokError::HttpStatus { status: response.status, url: request.url });
+        }
+
+        Ok(response)
+    }
+
+    async fn sign_url(
+        &self,
+        session: &TikTokSession,
+        url: &str,
+        params: &Option<RequestParams>,
+        body: Option<&RequestBody>,
+    ) -> Result<String> {
+        let final_url = match params {
+            Some(params) => {
+                let query = params.clone()
+                    .merged_over(RequestParams::for_session(&session.config))
+                    .to_query()?;
+                let mut url = Url::parse(url)?;
+                let query = match url.query() {
+                    Some(existing) if !existing.is_empty() => format!("{}&{}", existing, query),
+                    _ => query,
+                };
+                url.set_query(Some(&query));
+                url.to_string()
+            }
+            None => url.to_string(),
+        };
+
+        let user_agent = session.config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let signature = self.signer
+            .sign(&SignRequest {
+                url: &final_url,
+                user_agent,
+                body: body.map(|body| body.data.as_slice()),
+                session,
+            })
+            .await?;
+
+        // Signers may return any value, so the params are encoded like the rest of the query
+        let mut signed = Url::parse(&final_url)?;
+        if !signature.is_empty() {
+            let mut query = signed.query_pairs_mut();
+            for (key, value) in &signature {
+                query.append_pair(key, value);
+            }
+        }
+
+        Ok(signed.to_string())
     }
 
+    /// Shuts every session down, closing all of them even if some fail; launched browsers that
+    /// do not exit in time are killed. Browsers we only connected to keep running.
     pub async fn close(&self) -> Result<()> {
-        let mut sessions = self.sessions.lock().await;
-        for session in sessions.iter_mut() {
-            <chromiumoxide::Page as Clone>::clone(&session.page).close().await?;
-            Arc::try_unwrap(session.browser.clone()).unwrap().close().await?;
+        if let Some(task) = &self.supervisor_task {
+            task.abort();
+        }
+
+        let closing = self.sessions.drain()
+            .into_iter()
+            .enumerate()
+            .map(|(index, session)| async move { (index, browser::shutdown(session).await) });
+        let errors: Vec<_> = futures::future::join_all(closing).await
+            .into_iter()
+            .filter_map(|(index, result)| result.err().map(|e| (index, e)))
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TikTokError::CloseFailed(errors))
+        }
+    }
+}
+
+impl Drop for TikTokApi {
+    /// Safety net for an API dropped without `close()`: sessions are shut down in the background
+    /// when a runtime is available. Either way Chrome children are killed once their last handle drops.
+    fn drop(&mut self) {
+        if let Some(task) = self.supervisor_task.take() {
+            task.abort();
+        }
+
+        let sessions = self.sessions.drain();
+        if sessions.is_empty() {
+            return;
+        }
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                for session in sessions {
+                    let _ = browser::shutdown(session).await;
+                }
+            });
         }
-        sessions.clear();
-        Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,46 @@
+use std::path::Path;
+
+use crate::{
+    browser,
+    error::{Result, TikTokError},
+    types::SessionConfig,
+    TikTokApi, TikTokApiConfig,
+};
+
+impl TikTokApi {
+    /// Current state of every session, with cookies re-read from the browser.
+    pub async fn export_sessions(&self) -> Result<Vec<SessionConfig>> {
+        let mut exported = Vec::new();
+        for session in self.sessions.snapshot() {
+            let mut config = session.config.clone();
+            if let Some(page) = session.page.as_ref() {
+                let cookies = browser::read_cookies(page).await?;
+                if let Some(ms_token) = browser::ms_token(&cookies) {
+                    config.ms_token = Some(ms_token.to_string());
+                }
+                config.cookies = Some(cookies);
+            }
+            exported.push(config);
+        }
+        Ok(exported)
+    }
+
+    /// Writes `export_sessions` to `path` as JSON.
+    pub async fn save_sessions(&self, path: impl AsRef<Path>) -> Result<()> {
+        let sessions = self.export_sessions().await?;
+        tokio::fs::write(path, serde_json::to_vec_pretty(&sessions)?)
+            .await
+            .map_err(|e| TikTokError::Other(e.into()))
+    }
+
+    /// Starts the API with sessions restored from a file written by `save_sessions`.
+    ///
+    /// Saved sessions are reused first; fresh ones fill up to `num_sessions`.
+    pub async fn from_saved_sessions(config: TikTokApiConfig, path: impl AsRef<Path>) -> Result<Self> {
+        let data = tokio::fs::read(path)
+            .await
+            .map_err(|e| TikTokError::Other(e.into()))?;
+        let saved: Vec<SessionConfig> = serde_json::from_slice(&data)?;
+        Self::with_saved_sessions(config, saved).await
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+use crate::endpoints::EndpointClass;
+
+/// `requests` per `per`, with bursts of up to `requests`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl Rate {
+    pub fn per_second(requests: u32) -> Self {
+        Self { requests, per: Duration::from_secs(1) }
+    }
+
+    pub fn per_minute(requests: u32) -> Self {
+        Self { requests, per: Duration::from_secs(60) }
+    }
+}
+
+/// Parses `10/s`, `30/m` or `500/h`.
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (requests, unit) = s.split_once('/').ok_or("expected <requests>/<s|m|h>")?;
+        let requests = requests.trim().parse().map_err(|_| format!("{:?} is not a request count", requests))?;
+        let per = match unit.trim() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            other => return Err(format!("unknown unit {:?}, expected s, m or h", other)),
+        };
+        Ok(Self { requests, per })
+    }
+}
+
+/// Limits applied to every request. Unset levels are unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    pub global: Option<Rate>,
+    pub per_session: Option<Rate>,
+    pub per_proxy: Option<Rate>,
+    pub per_endpoint: HashMap<EndpointClass, Rate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketStats {
+    pub available: f64,
+    pub capacity: f64,
+    /// Requests let through so far.
+    pub acquired: u64,
+    /// Requests that had to wait for a token.
+    pub throttled: u64,
+    pub waited: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitStats {
+    pub global: Option<BucketStats>,
+    pub sessions: HashMap<usize, BucketStats>,
+    pub proxies: HashMap<String, BucketStats>,
+    pub endpoints: HashMap<EndpointClass, BucketStats>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    state: Mutex<BucketState>,
+    capacity: f64,
+    refill_per_sec: f64,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    acquired: u64,
+    throttled: u64,
+    waited: Duration,
+}
+
+impl TokenBucket {
+    fn new(rate: Rate) -> Self {
+        let capacity = rate.requests.max(1) as f64;
+        Self {
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+                acquired: 0,
+                throttled: 0,
+                waited: Duration::ZERO,
+            }),
+            capacity,
+            refill_per_sec: capacity / rate.per.as_secs_f64().max(f64::EPSILON),
+        }
+    }
+
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        state.last_refill = now;
+    }
+
+    async fn acquire(&self) {
+        let mut throttled = false;
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                self.refill(&mut state);
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    state.acquired += 1;
+                    if throttled {
+                        state.throttled += 1;
+                    }
+                    return;
+                }
+                let wait = Duration::from_secs_f64((1.0 - state.tokens) / self.refill_per_sec);
+                state.waited += wait;
+                wait
+            };
+            throttled = true;
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn stats(&self) -> BucketStats {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        BucketStats {
+            available: state.tokens,
+            capacity: self.capacity,
+            acquired: state.acquired,
+            throttled: state.throttled,
+            waited: state.waited,
+        }
+    }
+}
+
+/// Token buckets for the global, per-session, per-proxy and per-endpoint-class limits.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimitConfig,
+    global: Option<TokenBucket>,
+    endpoints: HashMap<EndpointClass, TokenBucket>,
+    sessions: Mutex<HashMap<usize, Arc<TokenBucket>>>,
+    proxies: Mutex<HashMap<String, Arc<TokenBucket>>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: RateLimitConfig) -> Self {
+        Self {
+            global: config.global.map(TokenBucket::new),
+            endpoints: config.per_endpoint
+                .iter()
+                .map(|(class, rate)| (*class, TokenBucket::new(*rate)))
+                .collect(),
+            sessions: Mutex::new(HashMap::new()),
+            proxies: Mutex::new(HashMap::new()),
+            config,
+        }
+    }
+
+    /// Waits on the limits that do not depend on which session serves the request.
+    pub(crate) async fn acquire_shared(&self, class: Option<EndpointClass>) {
+        if let Some(bucket) = class.and_then(|class| self.endpoints.get(&class)) {
+            bucket.acquire().await;
+        }
+        if let Some(bucket) = &self.global {
+            bucket.acquire().await;
+        }
+    }
+
+    /// Waits on the limits of the session that serves the request and its proxy.
+    pub(crate) async fn acquire_session(&self, index: usize, proxy: Option<&str>) {
+        if let Some(rate) = self.config.per_session {
+            let bucket = self.sessions.lock().unwrap()
+                .entry(index)
+                .or_insert_with(|| Arc::new(TokenBucket::new(rate)))
+                .clone();
+            bucket.acquire().await;
+        }
+        if let (Some(rate), Some(proxy)) = (self.config.per_proxy, proxy) {
+            let bucket = self.proxies.lock().unwrap()
+                .entry(proxy.to_string())
+                .or_insert_with(|| Arc::new(TokenBucket::new(rate)))
+                .clone();
+            bucket.acquire().await;
+        }
+    }
+
+    pub(crate) fn stats(&self) -> RateLimitStats {
+        RateLimitStats {
+            global: self.global.as_ref().map(TokenBucket::stats),
+            sessions: self.sessions.lock().unwrap()
+                .iter()
+                .map(|(index, bucket)| (*index, bucket.stats()))
+                .collect(),
+            proxies: self.proxies.lock().unwrap()
+                .iter()
+                .map(|(proxy, bucket)| (proxy.clone(), bucket.stats()))
+                .collect(),
+            endpoints: self.endpoints
+                .iter()
+                .map(|(class, bucket)| (*class, bucket.stats()))
+                .collect(),
+        }
+    }
+}
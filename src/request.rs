@@ -0,0 +1,210 @@
+pub use reqwest::Method;
+
+use serde::Serialize;
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    error::{ApiStatus, Result, TikTokError},
+    types::RequestParams,
+    TikTokApi,
+};
+
+/// A request body and the content type it is sent with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestBody {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl RequestBody {
+    pub fn json<T: Serialize + ?Sized>(value: &T) -> Result<Self> {
+        Ok(Self::raw("application/json", serde_json::to_vec(value)?))
+    }
+
+    pub fn form<T: Serialize + ?Sized>(value: &T) -> Result<Self> {
+        let encoded = serde_urlencoded::to_string(value)
+            .map_err(|e| TikTokError::Other(anyhow::anyhow!("Failed to encode form body: {}", e)))?;
+        Ok(Self::raw("application/x-www-form-urlencoded", encoded.into_bytes()))
+    }
+
+    pub fn raw(content_type: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Self { content_type: content_type.into(), data: data.into() }
+    }
+}
+
+/// A response as it came off the wire, before any JSON handling.
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    /// The URL after redirects.
+    pub url: String,
+    pub elapsed: Duration,
+    pub session_index: usize,
+    pub body: String,
+}
+
+impl RawResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Looks a header up by name, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses the body as a TikTok API answer, rejecting empty bodies and non-zero status codes.
+    pub fn json(&self) -> Result<serde_json::Value> {
+        if self.body.is_empty() {
+            return Err(TikTokError::EmptyResponse);
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&self.body)?;
+
+        if let Some(status) = self.api_status(&json) {
+            return Err(TikTokError::from_api_status(status, self.session_index, &self.url));
+        }
+
+        Ok(json)
+    }
+
+    /// The non-zero `status_code` (or item detail's `statusCode`) in `json`, if any.
+    fn api_status(&self, json: &serde_json::Value) -> Option<ApiStatus> {
+        let code = json.get("status_code")
+            .or_else(|| json.get("statusCode"))
+            .and_then(|code| code.as_i64().or_else(|| code.as_str()?.parse().ok()))
+            .filter(|&code| code != 0)?;
+
+        let message = json.get("status_msg")
+            .or_else(|| json.get("statusMsg"))
+            .and_then(|message| message.as_str())
+            .filter(|message| !message.is_empty())
+            .map(str::to_string);
+
+        let log_id = json.pointer("/log_pb/impr_id")
+            .or_else(|| json.pointer("/extra/logid"))
+            .and_then(|log_id| log_id.as_str())
+            .map(str::to_string)
+            .or_else(|| self.header("x-tt-logid").map(str::to_string));
+
+        let endpoint = url::Url::parse(&self.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| self.url.clone());
+
+        Some(ApiStatus { code, message, endpoint, log_id })
+    }
+}
+
+/// Everything `make_request` needs to send, sign and retry one API call.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiRequest {
+    pub(crate) method: Method,
+    pub(crate) url: String,
+    pub(crate) params: Option<RequestParams>,
+    pub(crate) headers: HashMap<String, String>,
+    pub(crate) body: Option<RequestBody>,
+    pub(crate) session_index: Option<usize>,
+}
+
+impl ApiRequest {
+    pub(crate) fn get(
+        url: &str,
+        params: Option<RequestParams>,
+        headers: Option<HashMap<String, String>>,
+        session_index: Option<usize>,
+    ) -> Self {
+        Self {
+            method: Method::GET,
+            url: url.to_string(),
+            params,
+            headers: headers.unwrap_or_default(),
+            body: None,
+            session_index,
+        }
+    }
+}
+
+/// Builds a request with any method and body, created by `TikTokApi::request`.
+///
+/// Errors from building the body are deferred until `send`.
+pub struct RequestBuilder<'a> {
+    api: &'a TikTokApi,
+    request: Result<ApiRequest>,
+}
+
+impl<'a> RequestBuilder<'a> {
+    pub(crate) fn new(api: &'a TikTokApi, method: Method, url: &str) -> Self {
+        Self {
+            api,
+            request: Ok(ApiRequest { method, ..ApiRequest::get(url, None, None, None) }),
+        }
+    }
+
+    pub fn params(self, params: RequestParams) -> Self {
+        self.map(|request| request.params = Some(params))
+    }
+
+    pub fn header(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.map(|request| {
+            request.headers.insert(name.into(), value.into());
+        })
+    }
+
+    pub fn headers(self, headers: HashMap<String, String>) -> Self {
+        self.map(|request| request.headers.extend(headers))
+    }
+
+    /// Pins the request to one session instead of leasing any idle one.
+    pub fn session(self, index: usize) -> Self {
+        self.map(|request| request.session_index = Some(index))
+    }
+
+    pub fn body(self, body: RequestBody) -> Self {
+        self.map(|request| request.body = Some(body))
+    }
+
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Self {
+        let body = RequestBody::json(value);
+        self.try_map(|request| {
+            request.body = Some(body?);
+            Ok(())
+        })
+    }
+
+    pub fn form<T: Serialize + ?Sized>(self, value: &T) -> Self {
+        let body = RequestBody::form(value);
+        self.try_map(|request| {
+            request.body = Some(body?);
+            Ok(())
+        })
+    }
+
+    pub async fn send(self) -> Result<serde_json::Value> {
+        self.api.execute(&self.request?, |response| response.json()).await
+    }
+
+    /// Sends the request and returns the response without parsing it.
+    pub async fn send_raw(self) -> Result<RawResponse> {
+        self.api.execute(&self.request?, Ok).await
+    }
+
+    fn map(self, f: impl FnOnce(&mut ApiRequest)) -> Self {
+        self.try_map(|request| {
+            f(request);
+            Ok(())
+        })
+    }
+
+    fn try_map(mut self, f: impl FnOnce(&mut ApiRequest) -> Result<()>) -> Self {
+        if let Ok(request) = &mut self.request {
+            if let Err(e) = f(request) {
+                self.request = Err(e);
+            }
+        }
+        self
+    }
+}
@@ -0,0 +1,44 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// How failed requests are retried. Applies to every `make_request` call.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of each delay that is randomised, from `0.0` to `1.0`.
+    pub jitter: f64,
+    /// Retry on a different session (and so a different proxy) when one is available.
+    pub rotate_session: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            jitter: 0.5,
+            rotate_session: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Default::default() }
+    }
+
+    /// Backoff before retry number `attempt` (starting at 1).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self.base_delay.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let delay = exponential.min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return delay;
+        }
+        delay.mul_f64(1.0 - jitter * rand::thread_rng().gen::<f64>())
+    }
+}
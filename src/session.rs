@@ -0,0 +1,409 @@
+use chromiumoxide::cdp::browser_protocol::{browser::BrowserContextId, target::CreateTargetParams};
+use chromiumoxide::{Browser, Page};
+use rand::{seq::SliceRandom, Rng};
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Arc, RwLock, Weak,
+    },
+};
+use tokio::sync::{Mutex, Notify};
+
+use crate::{
+    browser::{self, ProxyAuth},
+    error::{Result, TikTokError},
+    fingerprint::{self, FingerprintProfile},
+    proxy::{ProxyPool, ProxyStickiness, StickyKey},
+    stealth,
+    types::{SessionConfig, TikTokSession},
+    TikTokApiConfig,
+};
+
+struct Slot {
+    session: RwLock<Arc<TikTokSession>>,
+    busy: AtomicBool,
+    failures: AtomicU32,
+    quarantined: AtomicBool,
+}
+
+/// Sessions that are handed out one request at a time, so N sessions serve N requests in parallel.
+#[derive(Default)]
+pub struct SessionPool {
+    slots: RwLock<Vec<Arc<Slot>>>,
+    idle: Notify,
+}
+
+/// Exclusive use of one session, returned to the pool on drop.
+pub struct SessionLease {
+    pool: Arc<SessionPool>,
+    slot: Arc<Slot>,
+    session: Arc<TikTokSession>,
+    index: usize,
+}
+
+impl SessionPool {
+    pub fn push(&self, session: TikTokSession) {
+        self.slots.write().unwrap().push(Arc::new(Slot {
+            session: RwLock::new(Arc::new(session)),
+            busy: AtomicBool::new(false),
+            failures: AtomicU32::new(0),
+            quarantined: AtomicBool::new(false),
+        }));
+        self.idle.notify_waiters();
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_quarantined(&self, index: usize) -> bool {
+        self.slots.read().unwrap()
+            .get(index)
+            .is_some_and(|slot| slot.quarantined.load(Ordering::Acquire))
+    }
+
+    /// The current session in every slot, leased or not.
+    pub fn snapshot(&self) -> Vec<Arc<TikTokSession>> {
+        self.slots.read().unwrap()
+            .iter()
+            .map(|slot| slot.session.read().unwrap().clone())
+            .collect()
+    }
+
+    pub fn drain(&self) -> Vec<Arc<TikTokSession>> {
+        self.slots.write().unwrap()
+            .drain(..)
+            .map(|slot| slot.session.read().unwrap().clone())
+            .collect()
+    }
+
+    /// Waits for an idle, healthy session, or for the session at `index` when one is requested.
+    pub async fn lease(self: &Arc<Self>, index: Option<usize>) -> Result<SessionLease> {
+        self.lease_inner(index, None).await
+    }
+
+    /// Like `lease(None)`, but prefers any other idle session over the one at `avoid`.
+    pub async fn lease_avoiding(self: &Arc<Self>, avoid: usize) -> Result<SessionLease> {
+        self.lease_inner(None, Some(avoid)).await
+    }
+
+    async fn lease_inner(self: &Arc<Self>, index: Option<usize>, avoid: Option<usize>) -> Result<SessionLease> {
+        loop {
+            let notified = self.idle.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some(lease) = self.try_lease(index, avoid)? {
+                return Ok(lease);
+            }
+
+            notified.await;
+        }
+    }
+
+    fn try_lease(self: &Arc<Self>, index: Option<usize>, avoid: Option<usize>) -> Result<Option<SessionLease>> {
+        let slots = self.slots.read().unwrap();
+        if slots.is_empty() {
+            return Err(TikTokError::Other(anyhow::anyhow!("No sessions available")));
+        }
+
+        let candidates = match index {
+            Some(index) if index >= slots.len() => {
+                return Err(TikTokError::Other(anyhow::anyhow!("Invalid session index")));
+            }
+            Some(index) if slots[index].quarantined.load(Ordering::Acquire) => {
+                return Err(TikTokError::Other(anyhow::anyhow!("Session {} is quarantined", index)));
+            }
+            Some(index) => vec![index],
+            None => {
+                let mut healthy: Vec<usize> = (0..slots.len())
+                    .filter(|&i| !slots[i].quarantined.load(Ordering::Acquire))
+                    .collect();
+                if healthy.is_empty() {
+                    return Err(TikTokError::Other(anyhow::anyhow!("No healthy sessions available")));
+                }
+                healthy.shuffle(&mut rand::thread_rng());
+                healthy.sort_by_key(|&i| Some(i) == avoid);
+                healthy
+            }
+        };
+
+        Ok(candidates.into_iter().find_map(|index| self.acquire(&slots[index], index)))
+    }
+
+    /// A random healthy session to borrow the proxy and user agent of, left free for requests.
+    pub(crate) fn pick(&self) -> Result<(usize, Arc<TikTokSession>)> {
+        let slots = self.slots.read().unwrap();
+        let healthy: Vec<usize> = (0..slots.len())
+            .filter(|&i| !slots[i].quarantined.load(Ordering::Acquire))
+            .collect();
+        let Some(&index) = healthy.choose(&mut rand::thread_rng()) else {
+            return Err(TikTokError::Other(anyhow::anyhow!("No healthy sessions available")));
+        };
+        let session = slots[index].session.read().unwrap().clone();
+        Ok((index, session))
+    }
+
+    /// Takes the session at `index` out of rotation until the supervisor replaces it.
+    pub(crate) fn quarantine(&self, index: usize) {
+        if let Some(slot) = self.slots.read().unwrap().get(index) {
+            slot.quarantined.store(true, Ordering::Release);
+        }
+    }
+
+    /// Leases the session at `index` if it is idle, whether or not it is quarantined.
+    pub(crate) fn try_lease_any(self: &Arc<Self>, index: usize) -> Option<SessionLease> {
+        let slots = self.slots.read().unwrap();
+        slots.get(index).and_then(|slot| self.acquire(slot, index))
+    }
+
+    fn acquire(self: &Arc<Self>, slot: &Arc<Slot>, index: usize) -> Option<SessionLease> {
+        slot.busy.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).ok()?;
+        Some(SessionLease {
+            pool: self.clone(),
+            slot: slot.clone(),
+            session: slot.session.read().unwrap().clone(),
+            index,
+        })
+    }
+}
+
+impl SessionLease {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn record_success(&self) {
+        self.slot.failures.store(0, Ordering::Release);
+    }
+
+    /// Counts a failure and returns whether the session is now quarantined.
+    pub fn record_failure(&self, max_failures: u32) -> bool {
+        let failures = self.slot.failures.fetch_add(1, Ordering::AcqRel) + 1;
+        if failures >= max_failures && !self.slot.quarantined.swap(true, Ordering::AcqRel) {
+            log::warn!("Session {} quarantined after {} consecutive failures", self.index, failures);
+            return true;
+        }
+        false
+    }
+
+    pub fn quarantine(&self) {
+        self.slot.quarantined.store(true, Ordering::Release);
+    }
+
+    /// Swaps in a new session, clears the failure state and returns the old session.
+    pub(crate) fn replace(&mut self, session: TikTokSession) -> Arc<TikTokSession> {
+        let session = Arc::new(session);
+        *self.slot.session.write().unwrap() = session.clone();
+        self.slot.failures.store(0, Ordering::Release);
+        self.slot.quarantined.store(false, Ordering::Release);
+        std::mem::replace(&mut self.session, session)
+    }
+}
+
+impl Deref for SessionLease {
+    type Target = TikTokSession;
+
+    fn deref(&self) -> &TikTokSession {
+        &self.session
+    }
+}
+
+impl Drop for SessionLease {
+    fn drop(&mut self) {
+        self.slot.busy.store(false, Ordering::Release);
+        self.pool.idle.notify_waiters();
+    }
+}
+
+/// Builds ready-to-use sessions; shared with the supervisor so it can respawn dead ones.
+pub(crate) struct SessionFactory {
+    pub(crate) config: TikTokApiConfig,
+    pub(crate) needs_browser: bool,
+    pub(crate) proxies: Arc<ProxyPool>,
+    next_endpoint: AtomicUsize,
+    /// Browsers hosting several sessions; only the sessions hold strong handles.
+    shared_browsers: Mutex<Vec<SharedBrowser>>,
+}
+
+struct SharedBrowser {
+    browser: Weak<Browser>,
+    /// Credentials of the proxies its sessions use.
+    proxy_auth: ProxyAuth,
+}
+
+impl SharedBrowser {
+    fn upgrade(&self) -> Option<(Arc<Browser>, ProxyAuth)> {
+        Some((self.browser.upgrade()?, self.proxy_auth.clone()))
+    }
+}
+
+impl SessionFactory {
+    pub(crate) fn new(config: TikTokApiConfig, needs_browser: bool, proxies: Arc<ProxyPool>) -> Self {
+        Self {
+            config,
+            needs_browser,
+            proxies,
+            next_endpoint: AtomicUsize::new(0),
+            shared_browsers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Creates the session for `slot`, restoring `saved` state (cookies, msToken, device id, user agent)
+    /// when given. `slot` also picks the persistent profile directory, if profiles are configured.
+    pub(crate) async fn create(&self, slot: usize, saved: Option<SessionConfig>) -> Result<TikTokSession> {
+        self.build(slot, saved, true).await
+    }
+
+    /// A replacement for the session in `slot`, from a fresh profile since the old browser may still
+    /// hold the slot's one. It keeps the slot's proxy unless that proxy needs rotating.
+    pub(crate) async fn respawn(&self, slot: usize) -> Result<TikTokSession> {
+        self.build(slot, None, false).await
+    }
+
+    async fn build(&self, slot: usize, saved: Option<SessionConfig>, persistent_profile: bool) -> Result<TikTokSession> {
+        let mut config = match saved {
+            Some(mut saved) => {
+                if !self.proxies.is_empty() {
+                    let key = self.sticky_key(slot, &saved);
+                    saved.proxy = self.proxies.assign(key, saved.proxy.as_deref());
+                }
+                saved
+            }
+            None => self.fresh_config(slot),
+        };
+        if config.device_id.is_none() {
+            config.device_id = Some(generate_device_id());
+        }
+
+        if !self.needs_browser {
+            return Ok(TikTokSession { config, browser: None, page: None, context: None, owns_browser: false });
+        }
+
+        if let Some(endpoints) = self.config.connect.as_ref().filter(|endpoints| !endpoints.is_empty()) {
+            let endpoint = &endpoints[self.next_endpoint.fetch_add(1, Ordering::Relaxed) % endpoints.len()];
+            let browser = Arc::new(browser::connect(endpoint).await?);
+            let (context, page) = browser::open_context(&browser, &config).await?;
+            return self.prepare(browser, &ProxyAuth::default(), page, Some(context), false, config).await;
+        }
+
+        if self.config.launch.sessions_per_browser > 1 {
+            let (browser, proxy_auth, context, page) = self.open_shared_context(&config).await?;
+            return self.prepare(browser, &proxy_auth, page, Some(context), true, config).await;
+        }
+
+        let profile = self.config.launch.user_data_dir
+            .as_ref()
+            .filter(|_| persistent_profile)
+            .map(|dir| dir.join(format!("session-{}", slot)));
+        let browser = browser::launch(&self.config, Some(&config), profile.as_deref()).await?;
+        let page = browser.new_page(CreateTargetParams::default()).await?;
+        self.prepare(Arc::new(browser), &ProxyAuth::default(), page, None, true, config).await
+    }
+
+    /// Opens the session's context in a shared browser. A reused browser that cannot open one has
+    /// most likely crashed, so it takes no more sessions and a new browser is launched instead.
+    async fn open_shared_context(&self, config: &SessionConfig) -> Result<(Arc<Browser>, ProxyAuth, BrowserContextId, Page)> {
+        let mut browsers = self.shared_browsers.lock().await;
+        browsers.retain(|shared| shared.browser.strong_count() > 0);
+
+        let per_browser = self.config.launch.sessions_per_browser;
+        let with_room = browsers.iter().find(|shared| shared.browser.strong_count() < per_browser);
+        if let Some((browser, proxy_auth)) = with_room.and_then(SharedBrowser::upgrade) {
+            match browser::open_context(&browser, config).await {
+                Ok((context, page)) => return Ok((browser, proxy_auth, context, page)),
+                Err(e) => {
+                    log::warn!("Shared browser cannot open a context ({}), launching a new one", e);
+                    browsers.retain(|shared| shared.browser.as_ptr() != Arc::as_ptr(&browser));
+                }
+            }
+        }
+
+        let browser = Arc::new(browser::launch(&self.config, None, None).await?);
+        let proxy_auth = ProxyAuth::default();
+        browsers.push(SharedBrowser { browser: Arc::downgrade(&browser), proxy_auth: proxy_auth.clone() });
+        let (context, page) = browser::open_context(&browser, config).await?;
+        Ok((browser, proxy_auth, context, page))
+    }
+
+    /// Fingerprints and warms up the session's page. A page that does not get ready is closed
+    /// again, along with its context and any browser launched for it.
+    async fn prepare(
+        &self,
+        browser: Arc<Browser>,
+        proxy_auth: &ProxyAuth,
+        page: Page,
+        context: Option<BrowserContextId>,
+        owns_browser: bool,
+        mut config: SessionConfig,
+    ) -> Result<TikTokSession> {
+        let ready = async {
+            proxy_auth.register(&browser, &config).await?;
+            if self.config.stealth {
+                stealth::apply(&page, &mut config).await?;
+            }
+            let metadata = self.config.stealth.then(|| stealth::user_agent_metadata(&config)).flatten();
+            fingerprint::emulate(&page, &config, metadata).await?;
+            browser::restore_cookies(&page, &config).await?;
+            browser::warm_up(&page, &self.config, &mut config).await
+        }.await;
+
+        let session = TikTokSession {
+            config,
+            browser: Some(browser),
+            page: Some(Arc::new(page)),
+            context,
+            owns_browser,
+        };
+        if let Err(e) = ready {
+            if let Err(close) = browser::shutdown(Arc::new(session)).await {
+                log::warn!("Cannot close a session that failed to start: {}", close);
+            }
+            return Err(e);
+        }
+        Ok(session)
+    }
+
+    fn fresh_config(&self, slot: usize) -> SessionConfig {
+        let ms_token = self.config.ms_tokens
+            .as_ref()
+            .and_then(|tokens| tokens.choose(&mut rand::thread_rng()))
+            .cloned();
+
+        let mut config = SessionConfig {
+            user_agent: None,
+            language: String::new(),
+            platform: String::new(),
+            timezone: String::new(),
+            screen_width: 0,
+            screen_height: 0,
+            ms_token,
+            proxy: None,
+            cookies: None,
+            device_id: None,
+        };
+        FingerprintProfile::choose(self.config.fingerprints.as_deref()).apply_to(&mut config);
+        config.proxy = self.proxies.assign(self.sticky_key(slot, &config), None);
+        config
+    }
+
+    fn sticky_key(&self, slot: usize, config: &SessionConfig) -> StickyKey {
+        match self.config.proxy_pool.sticky {
+            ProxyStickiness::Session => StickyKey::Session(slot),
+            ProxyStickiness::Fingerprint => StickyKey::Fingerprint(config.user_agent.clone().unwrap_or_default()),
+        }
+    }
+}
+
+/// A web device id: 19 digits starting with 7, like the ones TikTok hands out.
+fn generate_device_id() -> String {
+    let mut rng = rand::thread_rng();
+    std::iter::once('7')
+        .chain((0..18).map(|_| char::from(b'0' + rng.gen_range(0..10))))
+        .collect()
+}
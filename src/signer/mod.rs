@@ -0,0 +1,93 @@
+pub mod page;
+pub mod remote;
+pub mod xbogus;
+
+use async_trait::async_trait;
+use std::{fmt, sync::Arc};
+
+use crate::types::TikTokSession;
+use crate::error::Result;
+
+pub use page::PageSigner;
+pub use remote::RemoteSigner;
+pub use xbogus::XBogusSigner;
+
+pub struct SignRequest<'a> {
+    pub url: &'a str,
+    pub user_agent: &'a str,
+    /// The encoded request body, for signers that cover it.
+    pub body: Option<&'a [u8]>,
+    pub session: &'a TikTokSession,
+}
+
+/// Produces the signature query parameters (e.g. `X-Bogus`) for a request URL.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    async fn sign(&self, request: &SignRequest<'_>) -> Result<Vec<(String, String)>>;
+}
+
+type SignFn = dyn Fn(&str, &str) -> Result<Vec<(String, String)>> + Send + Sync;
+
+/// Signs with a user-supplied closure taking the URL and user agent.
+pub struct FnSigner {
+    f: Box<SignFn>,
+}
+
+impl FnSigner {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&str, &str) -> Result<Vec<(String, String)>> + Send + Sync + 'static,
+    {
+        Self { f: Box::new(f) }
+    }
+}
+
+#[async_trait]
+impl Signer for FnSigner {
+    async fn sign(&self, request: &SignRequest<'_>) -> Result<Vec<(String, String)>> {
+        (self.f)(request.url, request.user_agent)
+    }
+}
+
+/// How `TikTokApi` signs request URLs.
+#[derive(Clone, Default)]
+pub enum SignerKind {
+    /// TikTok's SDK in the session's page, falling back to `Native`.
+    #[default]
+    Page,
+    /// Pure Rust X-Bogus.
+    Native,
+    /// An external signing service at the given URL.
+    Remote(String),
+    /// A user-supplied signer.
+    Custom(Arc<dyn Signer>),
+}
+
+impl SignerKind {
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: Fn(&str, &str) -> Result<Vec<(String, String)>> + Send + Sync + 'static,
+    {
+        SignerKind::Custom(Arc::new(FnSigner::new(f)))
+    }
+
+    pub fn build(&self) -> Arc<dyn Signer> {
+        match self {
+            SignerKind::Page => Arc::new(PageSigner::default()),
+            SignerKind::Native => Arc::new(XBogusSigner),
+            SignerKind::Remote(endpoint) => Arc::new(RemoteSigner::new(endpoint.clone())),
+            SignerKind::Custom(signer) => signer.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for SignerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerKind::Page => write!(f, "Page"),
+            SignerKind::Native => write!(f, "Native"),
+            SignerKind::Remote(endpoint) => f.debug_tuple("Remote").field(endpoint).finish(),
+            SignerKind::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
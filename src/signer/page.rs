@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+
+use crate::{
+    error::{Result, TikTokError},
+    signer::{SignRequest, Signer, XBogusSigner},
+};
+
+/// Signs with `window.byted_acrawler.frontierSign` inside the session's page.
+///
+/// Falls back to the native signer when the session has no page, the SDK is not loaded or the
+/// request has a body, which `frontierSign` does not cover.
+#[derive(Debug, Default, Clone)]
+pub struct PageSigner {
+    fallback: XBogusSigner,
+}
+
+#[async_trait]
+impl Signer for PageSigner {
+    async fn sign(&self, request: &SignRequest<'_>) -> Result<Vec<(String, String)>> {
+        let Some(page) = request.session.page.as_ref().filter(|_| request.body.is_none()) else {
+            return self.fallback.sign(request).await;
+        };
+
+        // Generate X-Bogus
+        let script = format!(
+            r#"
+            async () => {{
+                if (typeof window.byted_acrawler === 'undefined') {{
+                    return null;
+                }}
+                return window.byted_acrawler.frontierSign({});
+            }}
+            "#,
+            serde_json::to_string(request.url)?
+        );
+
+        let result = page.evaluate(script).await?;
+        if result.value().is_none_or(|v| v.is_null()) {
+            log::debug!("Signing SDK not loaded in page, falling back to native X-Bogus");
+            return self.fallback.sign(request).await;
+        }
+
+        let x_bogus = result.value()
+            .and_then(|v| v.as_str())
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok())
+            .and_then(|v| v.get("X-Bogus").and_then(|b| b.as_str()).map(|s| s.to_string()))
+            .ok_or(TikTokError::SignatureError)?;
+
+        Ok(vec![("X-Bogus".to_string(), x_bogus)])
+    }
+}
@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    error::{Result, TikTokError},
+    signer::{SignRequest, Signer},
+};
+
+/// Delegates signing to an external HTTP service.
+///
+/// The service receives `{"url": ..., "user_agent": ..., "body": ...}` as a JSON POST, `body`
+/// being the request body as text or absent, and must answer
+/// with `{"params": {"X-Bogus": ..., ...}}`; every entry of `params` is appended to the URL.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct RemoteSignRequest<'a> {
+    url: &'a str,
+    user_agent: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<std::borrow::Cow<'a, str>>,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    params: HashMap<String, String>,
+}
+
+impl RemoteSigner {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn sign(&self, request: &SignRequest<'_>) -> Result<Vec<(String, String)>> {
+        let response = self.client
+            .post(&self.endpoint)
+            .json(&RemoteSignRequest {
+                url: request.url,
+                user_agent: request.user_agent,
+                body: request.body.map(String::from_utf8_lossy),
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let signed: RemoteSignResponse = serde_json::from_str(&response.text().await?)?;
+        if signed.params.is_empty() {
+            return Err(TikTokError::SignatureError);
+        }
+
+        let mut params: Vec<_> = signed.params.into_iter().collect();
+        params.sort();
+        Ok(params)
+    }
+}
@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use base64::Engine;
+use md5::{Digest, Md5};
+use url::Url;
+
+use crate::{
+    error::Result,
+    signer::{SignRequest, Signer},
+};
+
+const ALPHABET: &[u8; 64] = b"Dkdpgh4ZKsQB80/Mfvw36XI1R25-WUAlEi7NLboqYTOPuzmFjJnryx9HVGcaStCe";
+const UA_KEY: &[u8] = &[0x00, 0x01, 0x0c];
+const CANVAS: u32 = 536919696;
+/// Where the SDK's `encoding_conversion` takes each packed value from in the shuffled list.
+const PACK_ORDER: [usize; 19] = [0, 10, 1, 11, 2, 12, 3, 13, 4, 14, 5, 15, 6, 16, 7, 17, 8, 18, 9];
+
+/// Computes X-Bogus natively, without a browser or TikTok's signing SDK.
+#[derive(Debug, Default, Clone)]
+pub struct XBogusSigner;
+
+#[async_trait]
+impl Signer for XBogusSigner {
+    async fn sign(&self, request: &SignRequest<'_>) -> Result<Vec<(String, String)>> {
+        let url = Url::parse(request.url)?;
+        let timestamp = chrono::Utc::now().timestamp() as u32;
+        let x_bogus = x_bogus(url.query().unwrap_or(""), request.user_agent, request.body, timestamp);
+        Ok(vec![("X-Bogus".to_string(), x_bogus)])
+    }
+}
+
+/// X-Bogus for a query string, user agent and optional request body at a given unix timestamp.
+pub fn x_bogus(query: &str, user_agent: &str, body: Option<&[u8]>, timestamp: u32) -> String {
+    let ua = base64::engine::general_purpose::STANDARD.encode(rc4(UA_KEY, user_agent.as_bytes()));
+    let ua_hash = md5(ua.as_bytes());
+    let body_hash = md5(&md5(body.unwrap_or_default()));
+    let query_hash = md5(&md5(query.as_bytes()));
+
+    let mut values = vec![
+        64, 0, 1, 12,
+        query_hash[14], query_hash[15],
+        body_hash[14], body_hash[15],
+        ua_hash[14], ua_hash[15],
+    ];
+    values.extend_from_slice(&timestamp.to_be_bytes());
+    values.extend_from_slice(&CANVAS.to_be_bytes());
+    let checksum = values.iter().fold(0, |acc, v| acc ^ v);
+    values.push(checksum);
+
+    // The SDK shuffles the values into even and odd positions, then packs them in its own order
+    let shuffled: Vec<u8> = values.iter().step_by(2).chain(values.iter().skip(1).step_by(2)).copied().collect();
+    let packed: Vec<u8> = PACK_ORDER.iter().map(|&i| shuffled[i]).collect();
+
+    let mut garbled = vec![2, 255];
+    garbled.extend(rc4(&[255], &packed));
+
+    garbled
+        .chunks(3)
+        .flat_map(|chunk| {
+            let n = (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32;
+            [18, 12, 6, 0].map(|shift| ALPHABET[((n >> shift) & 63) as usize] as char)
+        })
+        .collect()
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    Md5::digest(data).into()
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut s: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+        s.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(s[i as usize]);
+            s.swap(i as usize, j as usize);
+            byte ^ s[s[i as usize].wrapping_add(s[j as usize]) as usize]
+        })
+        .collect()
+}
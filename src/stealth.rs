@@ -0,0 +1,185 @@
+use chromiumoxide::Page;
+use chromiumoxide::cdp::browser_protocol::emulation::{UserAgentBrandVersion, UserAgentMetadata};
+use chromiumoxide::cdp::browser_protocol::page::AddScriptToEvaluateOnNewDocumentParams;
+use serde_json::json;
+
+use crate::{error::Result, types::SessionConfig};
+
+/// WebGL vendor and renderer pairs Chrome reports on each platform.
+const WINDOWS_GPUS: [(&str, &str); 3] = [
+    ("Google Inc. (NVIDIA)", "ANGLE (NVIDIA, NVIDIA GeForce GTX 1650 Direct3D11 vs_5_0 ps_5_0, D3D11)"),
+    ("Google Inc. (Intel)", "ANGLE (Intel, Intel(R) UHD Graphics 630 Direct3D11 vs_5_0 ps_5_0, D3D11)"),
+    ("Google Inc. (AMD)", "ANGLE (AMD, AMD Radeon RX 580 Series Direct3D11 vs_5_0 ps_5_0, D3D11)"),
+];
+const MAC_GPUS: [(&str, &str); 2] = [
+    ("Google Inc. (Intel Inc.)", "ANGLE (Intel Inc., Intel(R) Iris(TM) Plus Graphics OpenGL Engine, OpenGL 4.1)"),
+    ("Google Inc. (Intel Inc.)", "ANGLE (Intel Inc., Intel(R) UHD Graphics 630, OpenGL 4.1)"),
+];
+const LINUX_GPUS: [(&str, &str); 2] = [
+    ("Google Inc. (Intel)", "ANGLE (Intel, Mesa Intel(R) UHD Graphics 620 (KBL GT2), OpenGL 4.6)"),
+    ("Google Inc. (AMD)", "ANGLE (AMD, AMD Radeon RX 580 Series (polaris10, LLVM 15.0.7, DRM 3.49), OpenGL 4.6)"),
+];
+
+/// Patches the usual automation tells; runs before any page script with `fp` holding the fingerprint.
+const EVASIONS: &str = r#"
+const nativeToString = Function.prototype.toString;
+const arrayFind = Array.prototype.find;
+const disguised = new WeakMap();
+const disguise = (fn, name) => { disguised.set(fn, `function ${name}() { [native code] }`); return fn; };
+Function.prototype.toString = disguise(function toString() {
+    return disguised.get(this) || nativeToString.call(this);
+}, 'toString');
+const getter = (target, property, value) => Object.defineProperty(target, property, {
+    get: disguise(() => value, `get ${property}`),
+    configurable: true,
+});
+
+getter(Navigator.prototype, 'webdriver', false);
+getter(Navigator.prototype, 'platform', fp.platform);
+getter(Navigator.prototype, 'languages', Object.freeze(fp.languages));
+getter(Navigator.prototype, 'vendor', 'Google Inc.');
+getter(Navigator.prototype, 'hardwareConcurrency', fp.hardwareConcurrency);
+getter(Navigator.prototype, 'deviceMemory', 8);
+
+if (navigator.userAgentData) {
+    const brands = navigator.userAgentData.brands.map(brand =>
+        brand.brand === 'HeadlessChrome' ? { brand: 'Google Chrome', version: brand.version } : brand);
+    getter(Object.getPrototypeOf(navigator.userAgentData), 'brands', Object.freeze(brands));
+}
+
+if (navigator.plugins.length === 0) {
+    const mimeType = { type: 'application/pdf', suffixes: 'pdf', description: 'Portable Document Format' };
+    const plugins = ['PDF Viewer', 'Chrome PDF Viewer', 'Chromium PDF Viewer', 'Microsoft Edge PDF Viewer', 'WebKit built-in PDF']
+        .map(name => ({ name, filename: 'internal-pdf-viewer', description: 'Portable Document Format', length: 1, 0: mimeType }));
+    plugins.item = disguise(i => plugins[i] || null, 'item');
+    // Array methods are gone once the list becomes a PluginArray
+    plugins.namedItem = disguise(name => arrayFind.call(plugins, plugin => plugin.name === name) || null, 'namedItem');
+    plugins.refresh = disguise(() => undefined, 'refresh');
+    Object.setPrototypeOf(plugins, PluginArray.prototype);
+    const mimeTypes = [mimeType];
+    mimeTypes.item = disguise(i => mimeTypes[i] || null, 'item');
+    mimeTypes.namedItem = disguise(type => type === mimeType.type ? mimeType : null, 'namedItem');
+    Object.setPrototypeOf(mimeTypes, MimeTypeArray.prototype);
+    getter(Navigator.prototype, 'plugins', plugins);
+    getter(Navigator.prototype, 'mimeTypes', mimeTypes);
+}
+
+if (!window.chrome) {
+    window.chrome = {};
+}
+if (!window.chrome.runtime) {
+    window.chrome.runtime = { id: undefined, connect: disguise(() => {}, 'connect'), sendMessage: disguise(() => {}, 'sendMessage') };
+    window.chrome.app = { isInstalled: false, getDetails: disguise(() => null, 'getDetails'), getIsInstalled: disguise(() => false, 'getIsInstalled') };
+    window.chrome.csi = disguise(() => ({ onloadT: Date.now(), startE: Date.now(), pageT: performance.now(), tran: 15 }), 'csi');
+    window.chrome.loadTimes = disguise(() => ({ requestTime: Date.now() / 1000, firstPaintTime: Date.now() / 1000, wasFetchedViaSpdy: true, connectionInfo: 'h2' }), 'loadTimes');
+}
+
+const originalQuery = Permissions.prototype.query;
+Permissions.prototype.query = disguise(function query(parameters) {
+    if (parameters && parameters.name === 'notifications') {
+        const state = Notification.permission === 'default' ? 'prompt' : Notification.permission;
+        return Promise.resolve(Object.setPrototypeOf({ state, name: 'notifications', onchange: null }, PermissionStatus.prototype));
+    }
+    return originalQuery.call(this, parameters);
+}, 'query');
+
+for (const context of [WebGLRenderingContext, window.WebGL2RenderingContext].filter(Boolean)) {
+    const originalGetParameter = context.prototype.getParameter;
+    context.prototype.getParameter = disguise(function getParameter(parameter) {
+        if (parameter === 37445) return fp.webglVendor;
+        if (parameter === 37446) return fp.webglRenderer;
+        return originalGetParameter.call(this, parameter);
+    }, 'getParameter');
+}
+
+if (window.outerWidth === 0 || window.outerHeight === 0) {
+    getter(window, 'outerWidth', fp.screenWidth);
+    getter(window, 'outerHeight', fp.screenHeight + 85);
+}
+"#;
+
+/// The WebGL vendor and renderer for a session: a GPU that exists on its platform, the same one
+/// every time for the same device id.
+pub fn webgl_identity(session: &SessionConfig) -> (&'static str, &'static str) {
+    let gpus: &[(&str, &str)] = if session.platform.starts_with("Mac") {
+        &MAC_GPUS
+    } else if session.platform.starts_with("Linux") {
+        &LINUX_GPUS
+    } else {
+        &WINDOWS_GPUS
+    };
+    let seed = session.device_id.as_deref().unwrap_or_default()
+        .bytes()
+        .fold(0usize, |seed, byte| seed.wrapping_mul(31).wrapping_add(byte as usize));
+    gpus[seed % gpus.len()]
+}
+
+/// The evasion script for a session, with its fingerprint values baked in.
+pub fn script(session: &SessionConfig) -> String {
+    let (webgl_vendor, webgl_renderer) = webgl_identity(session);
+    let mut languages = vec![session.language.clone()];
+    if let Some((base, _)) = session.language.split_once('-') {
+        languages.push(base.to_string());
+    }
+
+    let fp = json!({
+        "platform": session.platform,
+        "languages": languages,
+        "hardwareConcurrency": 8,
+        "webglVendor": webgl_vendor,
+        "webglRenderer": webgl_renderer,
+        "screenWidth": session.screen_width,
+        "screenHeight": session.screen_height,
+    });
+    format!("(fp => {{{}}})({});", EVASIONS, fp)
+}
+
+/// The client hints Chrome reports along the session's user agent, so `navigator.userAgentData`
+/// and the `Sec-CH-UA` headers agree with it. `None` for user agents that are not Chrome.
+pub fn user_agent_metadata(session: &SessionConfig) -> Option<UserAgentMetadata> {
+    let user_agent = session.user_agent.as_deref()?;
+    let full_version = user_agent.split_whitespace().find_map(|token| token.strip_prefix("Chrome/"))?;
+    let major = full_version.split('.').next()?;
+
+    let (platform, platform_version) = if session.platform.starts_with("Mac") {
+        let version = user_agent.split("Mac OS X ").nth(1)
+            .and_then(|rest| rest.split([';', ')']).next())
+            .map(|version| version.replace('_', "."))
+            .unwrap_or_default();
+        ("macOS", version)
+    } else if session.platform.starts_with("Linux") {
+        ("Linux", String::new())
+    } else {
+        ("Windows", "10.0.0".to_string())
+    };
+    let brands = |grease: &str, chrome: &str| vec![
+        UserAgentBrandVersion::new("Not_A Brand", grease),
+        UserAgentBrandVersion::new("Chromium", chrome),
+        UserAgentBrandVersion::new("Google Chrome", chrome),
+    ];
+
+    Some(UserAgentMetadata {
+        brands: Some(brands("8", major)),
+        full_version_list: Some(brands("8.0.0.0", full_version)),
+        platform: platform.to_string(),
+        platform_version,
+        architecture: "x86".to_string(),
+        model: String::new(),
+        mobile: false,
+        bitness: Some("64".to_string()),
+        wow64: Some(false),
+    })
+}
+
+/// Installs the evasions on `page` before it loads anything, and takes the headless marker out of
+/// the session's user agent, which is the browser's own when the session has none.
+pub(crate) async fn apply(page: &Page, session: &mut SessionConfig) -> Result<()> {
+    let user_agent = match session.user_agent.take() {
+        Some(user_agent) => user_agent,
+        None => page.user_agent().await?,
+    };
+    session.user_agent = Some(user_agent.replace("HeadlessChrome", "Chrome"));
+
+    page.execute(AddScriptToEvaluateOnNewDocumentParams::new(script(session))).await?;
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+use crate::{
+    browser,
+    error::Result,
+    session::{SessionFactory, SessionPool},
+    types::TikTokSession,
+};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_EVENTS: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct SessionEvent {
+    pub session_index: usize,
+    pub at: DateTime<Utc>,
+    pub kind: SessionEventKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum SessionEventKind {
+    Quarantined { reason: String },
+    Replaced { reason: String },
+    RespawnFailed { error: String },
+}
+
+/// Probes sessions, respawns dead or quarantined ones and keeps a log of what it did.
+pub(crate) struct Supervisor {
+    pool: Arc<SessionPool>,
+    factory: Arc<SessionFactory>,
+    events: Mutex<VecDeque<SessionEvent>>,
+}
+
+impl Supervisor {
+    pub(crate) fn new(pool: Arc<SessionPool>, factory: Arc<SessionFactory>) -> Self {
+        Self { pool, factory, events: Mutex::new(VecDeque::new()) }
+    }
+
+    pub(crate) fn record(&self, session_index: usize, kind: SessionEventKind) {
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= MAX_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(SessionEvent { session_index, at: Utc::now(), kind });
+    }
+
+    pub(crate) fn events(&self) -> Vec<SessionEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Checks every idle session once. Sessions busy with a request are skipped.
+    pub(crate) async fn check(&self) -> Result<()> {
+        for index in 0..self.pool.len() {
+            let Some(mut lease) = self.pool.try_lease_any(index) else {
+                continue;
+            };
+
+            let reason = if self.pool.is_quarantined(index) {
+                "quarantined"
+            } else if lease.config.proxy.as_deref().is_some_and(|proxy| self.factory.proxies.needs_rotation(proxy)) {
+                "proxy rotated out"
+            } else if !probe(&lease).await {
+                "liveness probe failed"
+            } else {
+                continue;
+            };
+
+            match self.factory.respawn(index).await {
+                Ok(session) => {
+                    let old = lease.replace(session);
+                    retire(old).await;
+                    log::info!("Session {} replaced: {}", index, reason);
+                    self.record(index, SessionEventKind::Replaced { reason: reason.to_string() });
+                }
+                Err(e) => {
+                    lease.quarantine();
+                    log::warn!("Failed to respawn session {}: {}", index, e);
+                    self.record(index, SessionEventKind::RespawnFailed { error: e.to_string() });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn spawn(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let supervisor: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(supervisor) = supervisor.upgrade() else {
+                    break;
+                };
+                if let Err(e) = supervisor.check().await {
+                    log::warn!("Session health check failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+async fn probe(session: &TikTokSession) -> bool {
+    let Some(page) = session.page.as_ref() else {
+        return true;
+    };
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, page.evaluate("document.readyState")).await,
+        Ok(Ok(_))
+    )
+}
+
+async fn retire(session: Arc<TikTokSession>) {
+    if let Err(e) = browser::shutdown(session).await {
+        log::debug!("Retired session did not close cleanly: {}", e);
+    }
+}
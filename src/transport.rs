@@ -0,0 +1,241 @@
+use async_trait::async_trait;
+use base64::Engine;
+use chromiumoxide::cdp::browser_protocol::network::Cookie;
+use reqwest::Method;
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::{
+    error::{Result, TikTokError},
+    proxy::ProxyConfig,
+    types::TikTokSession,
+};
+
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+#[derive(Debug, Clone)]
+pub struct TransportRequest {
+    pub url: String,
+    pub method: Method,
+    pub headers: HashMap<String, String>,
+    /// Already encoded; its content type is in `headers`.
+    pub body: Option<Vec<u8>>,
+}
+
+/// What came back for a `TransportRequest`.
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    /// The URL after redirects, or the request URL if the transport cannot tell.
+    pub url: String,
+    pub body: String,
+}
+
+/// Carries a signed request to TikTok on behalf of a session and returns the response body.
+///
+/// Transports that only implement `send` report every answer as a 200, so HTTP errors never
+/// become `TikTokError::HttpStatus` and a 403 or 429 does not count against the proxy; only
+/// TikTok's status codes in the body are seen. Override `send_raw` to report the real status.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, session: &TikTokSession, request: &TransportRequest) -> Result<String>;
+
+    /// Like `send`, but with the status, headers and final URL. The default can see none of
+    /// them: it claims a 200 from the request URL with no headers.
+    async fn send_raw(&self, session: &TikTokSession, request: &TransportRequest) -> Result<TransportResponse> {
+        Ok(TransportResponse {
+            status: 200,
+            headers: HashMap::new(),
+            url: request.url.clone(),
+            body: self.send(session, request).await?,
+        })
+    }
+
+    /// Whether sessions for this transport need a browser and page.
+    fn needs_browser(&self) -> bool {
+        false
+    }
+}
+
+/// How `TikTokApi` talks to TikTok.
+#[derive(Clone, Default)]
+pub enum TransportKind {
+    /// `fetch` from inside the session's Chromium page.
+    #[default]
+    Browser,
+    /// Plain HTTP through reqwest, no browser is launched.
+    Http,
+    /// A user-supplied transport, e.g. an in-memory one for tests.
+    Custom(Arc<dyn Transport>),
+}
+
+impl TransportKind {
+    pub fn build(&self) -> Arc<dyn Transport> {
+        match self {
+            TransportKind::Browser => Arc::new(BrowserTransport),
+            TransportKind::Http => Arc::new(HttpTransport::default()),
+            TransportKind::Custom(transport) => transport.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for TransportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportKind::Browser => write!(f, "Browser"),
+            TransportKind::Http => write!(f, "Http"),
+            TransportKind::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BrowserTransport;
+
+#[async_trait]
+impl Transport for BrowserTransport {
+    async fn send(&self, session: &TikTokSession, request: &TransportRequest) -> Result<String> {
+        Ok(self.send_raw(session, request).await?.body)
+    }
+
+    async fn send_raw(&self, session: &TikTokSession, request: &TransportRequest) -> Result<TransportResponse> {
+        let page = session.page.as_ref()
+            .ok_or_else(|| TikTokError::BrowserError("Session has no page".to_string()))?;
+
+        // The body travels as base64 so binary payloads survive the trip into the page
+        let body = request.body
+            .as_ref()
+            .map(|body| base64::engine::general_purpose::STANDARD.encode(body));
+
+        // Prepare fetch request script
+        let fetch_script = format!(
+            r#"
+            async () => {{
+                const body = {};
+                const response = await fetch({}, {{
+                    method: {},
+                    headers: {},
+                    body: body === null ? undefined : Uint8Array.from(atob(body), c => c.charCodeAt(0)),
+                }});
+                return JSON.stringify({{
+                    status: response.status,
+                    url: response.url,
+                    headers: Object.fromEntries(response.headers.entries()),
+                    body: await response.text(),
+                }});
+            }}
+            "#,
+            serde_json::to_string(&body)?,
+            serde_json::to_string(&request.url)?,
+            serde_json::to_string(request.method.as_str())?,
+            serde_json::to_string(&request.headers)?
+        );
+
+        #[derive(serde::Deserialize)]
+        struct FetchResult {
+            status: u16,
+            url: String,
+            headers: HashMap<String, String>,
+            body: String,
+        }
+
+        let result = page.evaluate(fetch_script).await?;
+        let fetched: FetchResult = result.value()
+            .and_then(|v| v.as_str())
+            .map(serde_json::from_str)
+            .ok_or(TikTokError::EmptyResponse)??;
+
+        Ok(TransportResponse {
+            status: fetched.status,
+            headers: fetched.headers,
+            url: if fetched.url.is_empty() { request.url.clone() } else { fetched.url },
+            body: fetched.body,
+        })
+    }
+
+    fn needs_browser(&self) -> bool {
+        true
+    }
+}
+
+/// Sends requests with reqwest, using the session's user agent, cookies and proxy.
+#[derive(Debug, Default)]
+pub struct HttpTransport {
+    clients: std::sync::Mutex<HashMap<Option<String>, reqwest::Client>>,
+}
+
+impl HttpTransport {
+    fn client(&self, proxy: Option<&str>) -> Result<reqwest::Client> {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.get(&proxy.map(str::to_string)) {
+            return Ok(client.clone());
+        }
+
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(ProxyConfig::parse(proxy)?.to_reqwest()?);
+        }
+        let client = builder.build()?;
+        clients.insert(proxy.map(str::to_string), client.clone());
+        Ok(client)
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, session: &TikTokSession, request: &TransportRequest) -> Result<String> {
+        Ok(self.send_raw(session, request).await?.body)
+    }
+
+    async fn send_raw(&self, session: &TikTokSession, request: &TransportRequest) -> Result<TransportResponse> {
+        let client = self.client(session.config.proxy.as_deref())?;
+
+        let user_agent = session.config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let mut builder = client
+            .request(request.method.clone(), &request.url)
+            .header(reqwest::header::USER_AGENT, user_agent);
+
+        let url = url::Url::parse(&request.url)?;
+        let mut cookies: Vec<String> = session.config.cookies
+            .iter()
+            .flatten()
+            .filter(|cookie| sent_to(cookie, &url))
+            // The session's own msToken is appended below
+            .filter(|cookie| session.config.ms_token.is_none() || cookie.name != "msToken")
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        if let Some(ms_token) = &session.config.ms_token {
+            cookies.push(format!("msToken={}", ms_token));
+        }
+        if !cookies.is_empty() {
+            builder = builder.header(reqwest::header::COOKIE, cookies.join("; "));
+        }
+
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+
+        let response = builder.send().await?;
+        let status = response.status().as_u16();
+        let url = response.url().to_string();
+        let headers = response.headers()
+            .iter()
+            .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+            .collect();
+
+        Ok(TransportResponse { status, headers, url, body: response.text().await? })
+    }
+}
+
+/// Whether a browser would send `cookie` with a request to `url`.
+fn sent_to(cookie: &Cookie, url: &url::Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    let domain_matches = match cookie.domain.strip_prefix('.') {
+        Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+        None => host == cookie.domain,
+    };
+    domain_matches && url.path().starts_with(&cookie.path) && (!cookie.secure || url.scheme() == "https")
+}
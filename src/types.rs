@@ -1,6 +1,12 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use chromiumoxide::cdp::browser_protocol::network::Cookie;
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use crate::error::TikTokError;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionConfig {
@@ -12,20 +18,30 @@ pub struct SessionConfig {
     pub screen_height: u32,
     pub ms_token: Option<String>,
     pub proxy: Option<String>,
-    pub cookies: Option<HashMap<String, String>>,
+    /// Cookies as the browser reported them, with their domain, path, expiry and flags.
+    pub cookies: Option<Vec<Cookie>>,
+    #[serde(default)]
+    pub device_id: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct TikTokSession {
     pub config: SessionConfig,
-    pub browser: Arc<chromiumoxide::Browser>,
-    pub page: Arc<chromiumoxide::Page>,
+    pub browser: Option<Arc<chromiumoxide::Browser>>,
+    pub page: Option<Arc<chromiumoxide::Page>>,
+    /// The browser context the page lives in, when the session has one of its own.
+    pub context: Option<chromiumoxide::cdp::browser_protocol::browser::BrowserContextId>,
+    /// Whether the browser was launched by us; connected browsers are only detached from.
+    pub owns_browser: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserProfile {
+    #[serde(rename = "id", alias = "uid")]
     pub user_id: String,
+    #[serde(rename = "secUid", alias = "sec_uid")]
     pub sec_uid: String,
+    #[serde(rename = "uniqueId", alias = "unique_id")]
     pub username: String,
     #[serde(flatten)]
     pub raw_data: serde_json::Value,
@@ -86,49 +102,191 @@ pub struct Sound {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct RequestParams {
-    pub aid: String,
-    pub app_language: String,
-    pub app_name: String,
-    pub browser_language: String,
-    pub browser_name: String,
-    pub browser_online: String,
-    pub browser_platform: String,
-    pub browser_version: String,
-    pub channel: String,
-    pub cookie_enabled: String,
-    pub device_id: String,
-    pub device_platform: String,
-    pub focus_state: String,
-    pub from_page: String,
-    pub history_len: String,
-    pub is_fullscreen: String,
-    pub is_page_visible: String,
-    pub os: String,
-    pub priority_region: String,
-    pub referer: String,
-    pub region: String,
-    pub screen_height: String,
-    pub screen_width: String,
-    pub tz_name: String,
-    pub webcast_language: String,
-
-    
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser_online: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser_platform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cookie_enabled: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_platform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_len: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_fullscreen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_page_visible: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority_region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_height: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_width: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tz_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webcast_language: Option<String>,
+
     // API specific params
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sec_uid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub unique_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub count: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub aweme_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub comment_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub music_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub challenge_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub challenge_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub item_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub keyword: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_search_code: Option<String>,
 
     // Additional params
-    #[serde(flatten)]
+    #[serde(flatten, serialize_with = "sorted")]
     pub additional: HashMap<String, String>,
-}
\ No newline at end of file
+}
+
+/// Serializes `additional` by key so the query string is the same for the same params.
+fn sorted<S: Serializer>(additional: &HashMap<String, String>, serializer: S) -> Result<S::Ok, S::Error> {
+    additional.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+impl RequestParams {
+    /// The web app's constant params plus the browser, screen, region and device fields
+    /// derived from a session's config.
+    pub fn for_session(session: &SessionConfig) -> Self {
+        let user_agent = session.user_agent.as_deref().unwrap_or(crate::transport::DEFAULT_USER_AGENT);
+        let (language, region) = match session.language.split_once('-') {
+            Some((language, region)) => (language, region),
+            None => (session.language.as_str(), "US"),
+        };
+        let os = match session.platform.as_str() {
+            "MacIntel" => "mac",
+            "Win32" | "Windows" => "windows",
+            platform if platform.starts_with("Linux") => "linux",
+            _ => "windows",
+        };
+
+        RequestParams {
+            aid: Some("1988".to_string()),
+            app_language: Some(language.to_string()),
+            app_name: Some("tiktok_web".to_string()),
+            browser_language: Some(session.language.clone()),
+            browser_name: Some("Mozilla".to_string()),
+            browser_online: Some("true".to_string()),
+            browser_platform: Some(session.platform.clone()),
+            browser_version: Some(user_agent.trim_start_matches("Mozilla/").to_string()),
+            channel: Some("tiktok_web".to_string()),
+            cookie_enabled: Some("true".to_string()),
+            device_id: session.device_id.clone(),
+            device_platform: Some("web_pc".to_string()),
+            focus_state: Some("true".to_string()),
+            from_page: Some("user".to_string()),
+            history_len: Some("2".to_string()),
+            is_fullscreen: Some("false".to_string()),
+            is_page_visible: Some("true".to_string()),
+            os: Some(os.to_string()),
+            priority_region: Some(String::new()),
+            referer: Some(String::new()),
+            region: Some(region.to_string()),
+            screen_height: Some(session.screen_height.to_string()),
+            screen_width: Some(session.screen_width.to_string()),
+            tz_name: Some(session.timezone.clone()),
+            webcast_language: Some(language.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Encodes the params as a query string: fields in declaration order, then `additional`
+    /// sorted by key. Unset fields are left out; empty values are sent as `key=`.
+    pub fn to_query(&self) -> crate::error::Result<String> {
+        serde_urlencoded::to_string(self).map_err(|e| TikTokError::InvalidUrl(e.to_string()))
+    }
+
+    /// Keeps every field already set here and takes the rest from `defaults`.
+    pub fn merged_over(self, defaults: RequestParams) -> Self {
+        let mut additional = defaults.additional;
+        additional.extend(self.additional);
+
+        RequestParams {
+            aid: self.aid.or(defaults.aid),
+            app_language: self.app_language.or(defaults.app_language),
+            app_name: self.app_name.or(defaults.app_name),
+            browser_language: self.browser_language.or(defaults.browser_language),
+            browser_name: self.browser_name.or(defaults.browser_name),
+            browser_online: self.browser_online.or(defaults.browser_online),
+            browser_platform: self.browser_platform.or(defaults.browser_platform),
+            browser_version: self.browser_version.or(defaults.browser_version),
+            channel: self.channel.or(defaults.channel),
+            cookie_enabled: self.cookie_enabled.or(defaults.cookie_enabled),
+            device_id: self.device_id.or(defaults.device_id),
+            device_platform: self.device_platform.or(defaults.device_platform),
+            focus_state: self.focus_state.or(defaults.focus_state),
+            from_page: self.from_page.or(defaults.from_page),
+            history_len: self.history_len.or(defaults.history_len),
+            is_fullscreen: self.is_fullscreen.or(defaults.is_fullscreen),
+            is_page_visible: self.is_page_visible.or(defaults.is_page_visible),
+            os: self.os.or(defaults.os),
+            priority_region: self.priority_region.or(defaults.priority_region),
+            referer: self.referer.or(defaults.referer),
+            region: self.region.or(defaults.region),
+            screen_height: self.screen_height.or(defaults.screen_height),
+            screen_width: self.screen_width.or(defaults.screen_width),
+            tz_name: self.tz_name.or(defaults.tz_name),
+            webcast_language: self.webcast_language.or(defaults.webcast_language),
+            sec_uid: self.sec_uid.or(defaults.sec_uid),
+            unique_id: self.unique_id.or(defaults.unique_id),
+            count: self.count.or(defaults.count),
+            cursor: self.cursor.or(defaults.cursor),
+            aweme_id: self.aweme_id.or(defaults.aweme_id),
+            comment_id: self.comment_id.or(defaults.comment_id),
+            music_id: self.music_id.or(defaults.music_id),
+            challenge_id: self.challenge_id.or(defaults.challenge_id),
+            challenge_name: self.challenge_name.or(defaults.challenge_name),
+            item_id: self.item_id.or(defaults.item_id),
+            keyword: self.keyword.or(defaults.keyword),
+            web_search_code: self.web_search_code.or(defaults.web_search_code),
+            additional,
+        }
+    }
+}
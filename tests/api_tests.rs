@@ -1,6 +1,11 @@
+mod common;
+
+use common::FakeTransport;
 use rust_tok::{
     api::{HashtagApi, UserApi, VideoApi}, error::{Result, TikTokError}, TikTokApi, TikTokApiConfig
 };
+use rust_tok::transport::TransportKind;
+use std::sync::Arc;
 
 #[tokio::test]
 async fn test_user_info() -> Result<()> {
@@ -46,7 +51,7 @@ async fn test_error_handling() -> Result<()> {
     let api = TikTokApi::new(TikTokApiConfig::default()).await?;
     
     match api.user_info("this_user_definitely_does_not_exist_12345").await {
-        Err(TikTokError::NotFound) => (),
+        Err(TikTokError::NotFound(_)) => (),
         _ => panic!("Expected NotFound error"),
     }
     
@@ -87,7 +92,9 @@ async fn test_with_mocks() -> Result<()> {
     let mock_server = util::setup_mock_server().await;
     
     let config = TikTokApiConfig {
+        num_sessions: 1,
         base_url: mock_server.uri(),
+        transport: TransportKind::Http,
         ..Default::default()
     };
     
@@ -99,4 +106,28 @@ async fn test_with_mocks() -> Result<()> {
     
     api.close().await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_with_custom_transport() -> Result<()> {
+    let transport = Arc::new(FakeTransport::json(serde_json::json!({
+        "itemInfo": { "itemStruct": { "id": "7041997751718137094" } }
+    })));
+
+    let config = TikTokApiConfig {
+        num_sessions: 1,
+        transport: TransportKind::Custom(transport.clone()),
+        ..Default::default()
+    };
+
+    let api = TikTokApi::new(config).await?;
+    let video = api.video_info("7041997751718137094").await?;
+    assert_eq!(video.id, "7041997751718137094");
+
+    let sent = transport.sent();
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].request.url.contains("/api/item/detail/"));
+
+    api.close().await?;
+    Ok(())
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use common::FakeTransport;
+use rust_tok::{
+    api::VideoApi,
+    captcha::{CaptchaChallenge, CaptchaHandler, CaptchaResolution, RotateSessionHandler},
+    error::{Result, TikTokError},
+    retry::RetryPolicy,
+    supervisor::SessionEventKind,
+    transport::TransportKind,
+    TikTokApi, TikTokApiConfig,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+const VERIFY_BODY: &str = r#"{"code":"10000","type":"verify","subtype":"slide","region":"va","verify_event":"","fp":"verify_abc"}"#;
+
+/// Answers with a captcha on the first request of the first session it sees, and on every
+/// request of that session when `sticky` is set.
+fn captcha_transport(sticky: bool) -> Arc<FakeTransport> {
+    let flagged = Mutex::new(None);
+    Arc::new(FakeTransport::new(move |sent| {
+        let mut flagged = flagged.lock().unwrap();
+        if sent.call == 0 {
+            *flagged = Some(sent.session_id);
+        }
+        if *flagged == Some(sent.session_id) && (sent.call == 0 || sticky) {
+            return Ok(VERIFY_BODY.to_string());
+        }
+        Ok(serde_json::json!({ "itemInfo": { "itemStruct": { "id": "1" } } }).to_string())
+    }))
+}
+
+#[derive(Default)]
+struct CountingSolver {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl CaptchaHandler for CountingSolver {
+    async fn handle(&self, challenge: &CaptchaChallenge<'_>) -> Result<CaptchaResolution> {
+        assert_eq!(challenge.subtype, Some("slide"));
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(CaptchaResolution::Solved)
+    }
+}
+
+fn config(sessions: usize, transport: Arc<FakeTransport>) -> TikTokApiConfig {
+    TikTokApiConfig {
+        num_sessions: sessions,
+        health_check_interval: None,
+        transport: TransportKind::Custom(transport),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_captcha_is_reported_with_session() -> Result<()> {
+    let api = TikTokApi::new(config(1, captcha_transport(true))).await?;
+
+    match api.video_info("1").await {
+        Err(TikTokError::CaptchaRequired { session_index, url, subtype }) => {
+            assert_eq!(session_index, 0);
+            assert!(url.contains("/api/item/detail/"));
+            assert_eq!(subtype.as_deref(), Some("slide"));
+        }
+        other => panic!("Expected CaptchaRequired, got {:?}", other.map(|v| v.id)),
+    }
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_request_resumes_after_handler_solves() -> Result<()> {
+    let transport = captcha_transport(false);
+    let solver = Arc::new(CountingSolver::default());
+    let api = TikTokApi::new(TikTokApiConfig {
+        captcha_handler: Some(solver.clone()),
+        proxies: Some(vec!["http://10.0.0.1:8080".to_string()]),
+        ..config(1, transport.clone())
+    }).await?;
+
+    api.video_info("1").await?;
+    assert_eq!(solver.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(transport.calls(), 2);
+
+    // The solved captcha is no mark against the proxy or the session
+    let stats = &api.proxy_stats()[0];
+    assert_eq!((stats.bans, stats.successes), (0, 1));
+    assert!(!stats.cooling_down);
+    assert!(api.session_events().is_empty());
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rotate_handler_moves_to_another_session() -> Result<()> {
+    let api = TikTokApi::new(TikTokApiConfig {
+        captcha_handler: Some(Arc::new(RotateSessionHandler)),
+        ..config(2, captcha_transport(true))
+    }).await?;
+
+    api.video_info("1").await?;
+    let events = api.session_events();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0].kind, SessionEventKind::Quarantined { .. }));
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_center_html_is_detected() -> Result<()> {
+    let api = |body| TikTokApi::new(TikTokApiConfig {
+        num_sessions: 1,
+        health_check_interval: None,
+        retry: RetryPolicy::none(),
+        transport: TransportKind::Custom(Arc::new(FakeTransport::body(body))),
+        ..Default::default()
+    });
+
+    for body in [
+        r#"<html><script>location.href="https://www.tiktok.com/verify?from=api"</script></html>"#,
+        r#"<html><body><div id="captcha_container"></div></body></html>"#,
+    ] {
+        let api = api(body).await?;
+        assert!(matches!(api.video_info("1").await, Err(TikTokError::CaptchaRequired { .. })), "{}", body);
+        api.close().await?;
+    }
+
+    // A page that merely mentions captchas is not a challenge
+    let api = api(r#"<html><script src="/static/captcha-helper.js"></script></html>"#).await?;
+    assert!(matches!(api.video_info("1").await, Err(TikTokError::InvalidJSON(_))));
+
+    api.close().await?;
+    Ok(())
+}
@@ -0,0 +1,445 @@
+//! Stand-ins for Chrome's DevTools endpoint and for TikTok itself, so sessions can be set up,
+//! used and shut down without a real browser or network.
+#![allow(dead_code)]
+
+use async_tungstenite::tungstenite::Message;
+use futures::{SinkExt, StreamExt};
+use rust_tok::{
+    error::{Result, TikTokError},
+    transport::{Transport, TransportRequest},
+    types::{SessionConfig, TikTokSession},
+};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::watch};
+
+/// A request `FakeTransport` received.
+#[derive(Debug, Clone)]
+pub struct Sent {
+    /// Numbers every request from 0, in the order they arrived.
+    pub call: usize,
+    /// Tells sessions apart: the address of the session the request went out on.
+    pub session_id: usize,
+    pub session: SessionConfig,
+    pub request: TransportRequest,
+}
+
+type Respond = Box<dyn Fn(&Sent) -> Result<String> + Send + Sync>;
+
+/// Answers requests from a closure instead of TikTok, and records every one of them.
+pub struct FakeTransport {
+    respond: Respond,
+    errors: Mutex<Vec<TikTokError>>,
+    delay: Option<Duration>,
+    needs_browser: bool,
+    sent: Mutex<Vec<Sent>>,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl FakeTransport {
+    pub fn new(respond: impl Fn(&Sent) -> Result<String> + Send + Sync + 'static) -> Self {
+        Self {
+            respond: Box::new(respond),
+            errors: Mutex::new(Vec::new()),
+            delay: None,
+            needs_browser: false,
+            sent: Mutex::new(Vec::new()),
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Answers every request with `body`.
+    pub fn json(body: Value) -> Self {
+        Self::body(body.to_string())
+    }
+
+    pub fn body(body: impl Into<String>) -> Self {
+        let body = body.into();
+        Self::new(move |_| Ok(body.clone()))
+    }
+
+    /// Answers the first requests with these errors, in order, before responding as usual.
+    pub fn failing_with(mut self, errors: Vec<TikTokError>) -> Self {
+        self.errors = Mutex::new(errors);
+        self
+    }
+
+    /// Holds every request this long before answering it.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Makes sessions launch a browser and page, as the browser transport does.
+    pub fn needs_browser(mut self) -> Self {
+        self.needs_browser = true;
+        self
+    }
+
+    pub fn sent(&self) -> Vec<Sent> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn calls(&self) -> usize {
+        self.sent.lock().unwrap().len()
+    }
+
+    /// The most requests that were ever in flight at once.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for FakeTransport {
+    async fn send(&self, session: &TikTokSession, request: &TransportRequest) -> Result<String> {
+        let sent = {
+            let mut log = self.sent.lock().unwrap();
+            let sent = Sent {
+                call: log.len(),
+                session_id: session as *const TikTokSession as usize,
+                session: session.config.clone(),
+                request: request.clone(),
+            };
+            log.push(sent.clone());
+            sent
+        };
+
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        let error = {
+            let mut errors = self.errors.lock().unwrap();
+            (!errors.is_empty()).then(|| errors.remove(0))
+        };
+        match error {
+            Some(error) => Err(error),
+            None => (self.respond)(&sent),
+        }
+    }
+
+    fn needs_browser(&self) -> bool {
+        self.needs_browser
+    }
+}
+
+pub const HEADLESS_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.0.0 Safari/537.36";
+
+/// How the fake browser treats particular CDP methods.
+#[derive(Default, Clone)]
+pub struct Behaviour {
+    /// Methods that are never answered.
+    pub hang: Vec<&'static str>,
+    /// Methods that are answered with a CDP error.
+    pub fail: Vec<&'static str>,
+    /// Answers that replace the usual result of a method.
+    pub results: HashMap<&'static str, Value>,
+    /// Events sent to the page session right after answering a method.
+    pub events_after: HashMap<&'static str, Vec<(&'static str, Value)>>,
+}
+
+/// A CDP command the fake browser received.
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub connection: usize,
+    pub method: String,
+    pub params: Value,
+}
+
+pub struct FakeChrome {
+    /// The `ws://` DevTools URL to connect to.
+    pub url: String,
+    pub commands: Arc<Mutex<Vec<Command>>>,
+    dir: PathBuf,
+    crashes: watch::Sender<usize>,
+}
+
+impl FakeChrome {
+    pub async fn start(behaviour: Behaviour) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let dir = std::env::temp_dir().join(format!("rust-tok-fake-chrome-{}-{}", std::process::id(), port));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let (crashes, _) = watch::channel(0);
+        let log = commands.clone();
+        let crashed = crashes.clone();
+        tokio::spawn(async move {
+            let mut connection = 0;
+            while let Ok((stream, _)) = listener.accept().await {
+                connection += 1;
+                let Ok(socket) = async_tungstenite::tokio::accept_async(stream).await else {
+                    continue;
+                };
+                tokio::spawn(serve(socket, connection, behaviour.clone(), log.clone(), crashed.subscribe()));
+            }
+        });
+
+        Self {
+            url: format!("ws://127.0.0.1:{}/devtools/browser/fake", port),
+            commands,
+            dir,
+            crashes,
+        }
+    }
+
+    /// A "Chrome" executable that announces this endpoint and then idles, recording its pid.
+    pub fn executable(&self) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = self.dir.join("chrome");
+        let script = format!(
+            "#!/bin/sh\necho $$ >> {}\necho \"DevTools listening on {}\" >&2\nexec sleep 600\n",
+            self.dir.join("pids").display(),
+            self.url
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// Pids of every process launched from `executable`.
+    pub fn launched(&self) -> Vec<u32> {
+        std::fs::read_to_string(self.dir.join("pids"))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect()
+    }
+
+    /// Kills every launched process and drops the DevTools connections open so far, the way a
+    /// crashed browser would. Later connections are served as usual.
+    pub fn crash(&self) {
+        for pid in self.launched() {
+            kill(pid);
+        }
+        self.crashes.send_modify(|crashes| *crashes += 1);
+    }
+
+    pub fn calls(&self, method: &str) -> Vec<Command> {
+        self.commands.lock().unwrap().iter().filter(|command| command.method == method).cloned().collect()
+    }
+}
+
+/// A cookie as `Network.getCookies` reports it.
+pub fn cookie(name: &str, value: &str, domain: &str) -> Value {
+    json!({
+        "name": name,
+        "value": value,
+        "domain": domain,
+        "path": "/",
+        "expires": -1.0,
+        "size": name.len() + value.len(),
+        "httpOnly": false,
+        "secure": true,
+        "session": true,
+        "priority": "Medium",
+        "sameParty": false,
+        "sourceScheme": "Secure",
+        "sourcePort": 443,
+    })
+}
+
+pub fn is_running(pid: u32) -> bool {
+    // Reaped processes are gone from /proc; zombies are not running either
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .map(|stat| !stat.contains(") Z"))
+        .unwrap_or(false)
+}
+
+pub fn kill(pid: u32) {
+    std::process::Command::new("kill").arg("-9").arg(pid.to_string()).status().unwrap();
+}
+
+#[derive(Default)]
+struct State {
+    next_id: usize,
+    /// Browser context of every target.
+    targets: HashMap<String, Option<String>>,
+    /// Target of every session.
+    sessions: HashMap<String, String>,
+}
+
+impl State {
+    fn next(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}-{}", prefix, self.next_id)
+    }
+}
+
+fn target_info(target_id: &str, context: &Option<String>) -> Value {
+    json!({
+        "targetId": target_id,
+        "type": "page",
+        "title": "",
+        "url": "about:blank",
+        "attached": true,
+        "canAccessOpener": false,
+        "browserContextId": context,
+    })
+}
+
+fn frame(frame_id: &str, loader_id: &str, url: &str) -> Value {
+    json!({
+        "id": frame_id,
+        "loaderId": loader_id,
+        "url": url,
+        "domainAndRegistry": "",
+        "securityOrigin": url,
+        "mimeType": "text/html",
+        "secureContextType": "Secure",
+        "crossOriginIsolatedContextType": "NotIsolated",
+        "gatedAPIFeatures": [],
+    })
+}
+
+fn lifecycle(frame_id: &str, loader_id: &str, name: &str) -> (String, Value) {
+    ("Page.lifecycleEvent".to_string(), json!({ "frameId": frame_id, "loaderId": loader_id, "name": name, "timestamp": 0.0 }))
+}
+
+async fn serve<S>(
+    socket: async_tungstenite::WebSocketStream<S>,
+    connection: usize,
+    behaviour: Behaviour,
+    log: Arc<Mutex<Vec<Command>>>,
+    mut crashed: watch::Receiver<usize>,
+) where
+    S: futures::AsyncRead + futures::AsyncWrite + Unpin,
+{
+    let (mut sink, mut stream) = socket.split();
+    let mut state = State::default();
+
+    loop {
+        let message = tokio::select! {
+            message = stream.next() => message,
+            _ = crashed.changed() => return,
+        };
+        let Some(Ok(message)) = message else {
+            return;
+        };
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let request: Value = serde_json::from_str(&text).unwrap();
+        let id = request["id"].clone();
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        let params = request["params"].clone();
+        let session = request["sessionId"].as_str().map(str::to_string);
+        log.lock().unwrap().push(Command { connection, method: method.clone(), params: params.clone() });
+
+        if behaviour.hang.contains(&method.as_str()) {
+            continue;
+        }
+        if behaviour.fail.contains(&method.as_str()) {
+            let error = json!({ "id": id, "error": { "code": -32000, "message": format!("{} failed", method) } });
+            if sink.send(Message::Text(error.to_string())).await.is_err() {
+                return;
+            }
+            continue;
+        }
+
+        let target = session.as_ref().and_then(|session| state.sessions.get(session)).cloned().unwrap_or_default();
+        // Events that must arrive before the answer, and events that follow it
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        let result = match method.as_str() {
+            "Target.createBrowserContext" => json!({ "browserContextId": state.next("context") }),
+            "Target.createTarget" => {
+                let target_id = state.next("page");
+                let context = params["browserContextId"].as_str().map(str::to_string);
+                before.push(("Target.targetCreated".to_string(), json!({ "targetInfo": target_info(&target_id, &context) })));
+                state.targets.insert(target_id.clone(), context);
+                json!({ "targetId": target_id })
+            }
+            "Target.attachToTarget" => {
+                let target_id = params["targetId"].as_str().unwrap_or_default().to_string();
+                let session_id = state.next("session");
+                let context = state.targets.get(&target_id).cloned().flatten();
+                before.push(("Target.attachedToTarget".to_string(), json!({
+                    "sessionId": session_id,
+                    "targetInfo": target_info(&target_id, &context),
+                    "waitingForDebugger": false,
+                })));
+                state.sessions.insert(session_id.clone(), target_id);
+                json!({ "sessionId": session_id })
+            }
+            "Target.getTargetInfo" => json!({ "targetInfo": target_info(&target, &None) }),
+            "Page.getFrameTree" => json!({ "frameTree": { "frame": frame(&target, "loader-0", "about:blank") } }),
+            "Page.setLifecycleEventsEnabled" => {
+                after.push(lifecycle(&target, "loader-0", "init"));
+                after.push(lifecycle(&target, "loader-0", "load"));
+                json!({})
+            }
+            "Runtime.enable" => {
+                after.push(("Runtime.executionContextCreated".to_string(), json!({ "context": {
+                    "id": 1, "origin": "", "name": "", "uniqueId": "1",
+                    "auxData": { "frameId": target, "isDefault": true, "type": "default" },
+                } })));
+                json!({})
+            }
+            "Page.navigate" => {
+                let loader_id = state.next("loader");
+                let url = params["url"].as_str().unwrap_or_default();
+                after.push(lifecycle(&target, &loader_id, "init"));
+                after.push(("Page.frameNavigated".to_string(), json!({ "frame": frame(&target, &loader_id, url), "type": "Navigation" })));
+                after.push(lifecycle(&target, &loader_id, "DOMContentLoaded"));
+                after.push(lifecycle(&target, &loader_id, "load"));
+                json!({ "frameId": target, "loaderId": loader_id })
+            }
+            "Page.createIsolatedWorld" => json!({ "executionContextId": 2 }),
+            "Page.addScriptToEvaluateOnNewDocument" => json!({ "identifier": state.next("script") }),
+            "Runtime.evaluate" | "Runtime.callFunctionOn" => json!({ "result": { "type": "boolean", "value": true } }),
+            "Network.getCookies" => json!({ "cookies": [] }),
+            "Browser.getVersion" => json!({
+                "protocolVersion": "1.3",
+                "product": "HeadlessChrome/120.0.0.0",
+                "revision": "",
+                "userAgent": HEADLESS_USER_AGENT,
+                "jsVersion": "",
+            }),
+            _ => json!({}),
+        };
+        let result = behaviour.results.get(method.as_str()).cloned().unwrap_or(result);
+        for (event, params) in behaviour.events_after.get(method.as_str()).into_iter().flatten() {
+            after.push((event.to_string(), params.clone()));
+        }
+
+        let mut messages: Vec<Value> = before.into_iter()
+            .map(|(event, params)| json!({ "method": event, "params": params }))
+            .collect();
+        messages.push(json!({ "id": id, "result": result }));
+        messages.extend(after.into_iter().map(|(event, params)| {
+            let mut message = json!({ "method": event, "params": params });
+            // Events of the browser session carry no session id at all
+            if let Some(session) = &session {
+                message["sessionId"] = json!(session);
+            }
+            message
+        }));
+        for message in messages {
+            if sink.send(Message::Text(message.to_string())).await.is_err() {
+                return;
+            }
+        }
+
+        if method == "Browser.close" {
+            return;
+        }
+    }
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::FakeTransport;
+use rust_tok::{
+    api::{CommentApi, HashtagApi, SearchApi, SoundApi, TrendingApi, UserApi, VideoApi},
+    endpoints::Endpoint,
+    error::Result,
+    transport::TransportKind,
+    types::{RequestParams, SessionConfig},
+    TikTokApi, TikTokApiConfig,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// The params each endpoint must carry on top of the session defaults.
+fn expected_fields(endpoint: Endpoint) -> &'static [(&'static str, &'static str)] {
+    match endpoint {
+        Endpoint::UserDetail => &[("unique_id", "therock")],
+        Endpoint::UserPosts | Endpoint::UserLiked => &[("sec_uid", "MS4w/+="), ("count", "5"), ("cursor", "0")],
+        Endpoint::ItemDetail => &[("aweme_id", "7041997751718137094")],
+        Endpoint::RelatedItems => &[("aweme_id", "7041997751718137094"), ("count", "5")],
+        Endpoint::CommentList => &[("aweme_id", "7041997751718137094"), ("count", "5"), ("cursor", "0")],
+        Endpoint::CommentDetail => &[("comment_id", "7042")],
+        Endpoint::CommentReplies => &[("comment_id", "7042"), ("count", "5"), ("cursor", "0")],
+        Endpoint::MusicDetail => &[("music_id", "6689")],
+        Endpoint::MusicItems => &[("music_id", "6689"), ("count", "5"), ("cursor", "0")],
+        Endpoint::ChallengeDetail => &[("challenge_name", "funny cats")],
+        Endpoint::ChallengeItems => &[("challenge_id", "1234"), ("count", "5"), ("cursor", "0")],
+        Endpoint::Recommend => &[("from_page", "fyp"), ("count", "5")],
+        Endpoint::SearchUser | Endpoint::SearchVideo => &[("keyword", "funny cats & dogs"), ("from_page", "search"), ("cursor", "0")],
+    }
+}
+
+async fn record_every_endpoint() -> Result<Vec<(SessionConfig, String)>> {
+    let transport = Arc::new(FakeTransport::body("{}"));
+    let api = TikTokApi::new(TikTokApiConfig {
+        num_sessions: 1,
+        health_check_interval: None,
+        transport: TransportKind::Custom(transport.clone()),
+        ..Default::default()
+    }).await?;
+
+    let video_id = "7041997751718137094";
+    let _ = api.user_info("therock").await;
+    let _ = api.user_videos("MS4w/+=", 5).await;
+    let _ = api.user_liked_videos("MS4w/+=", 5).await;
+    let _ = api.video_info(video_id).await;
+    let _ = api.related_videos(video_id, 5).await;
+    let _ = api.video_comments(video_id, 5).await;
+    let _ = api.comment_info("7042").await;
+    let _ = api.comment_replies("7042", 5).await;
+    let _ = api.sound_info("6689").await;
+    let _ = api.sound_videos("6689", 5).await;
+    let _ = api.hashtag_info("funny cats").await;
+    let _ = api.hashtag_videos("1234", 5).await;
+    let _ = api.trending_videos(5).await;
+    let _ = api.search_users("funny cats & dogs", 5).await;
+    let _ = api.search_videos("funny cats & dogs", 5).await;
+
+    api.close().await?;
+    Ok(transport.sent().into_iter().map(|sent| (sent.session, sent.request.url)).collect())
+}
+
+fn split_signature(url: &str) -> (&str, &str) {
+    let query = url.split_once('?').map(|(_, query)| query).unwrap_or_default();
+    query.rsplit_once("&X-Bogus=").expect("request is not signed")
+}
+
+#[tokio::test]
+async fn test_every_endpoint_is_requested() -> Result<()> {
+    let requests = record_every_endpoint().await?;
+
+    for endpoint in Endpoint::ALL {
+        assert!(
+            requests.iter().any(|(_, url)| Endpoint::from_url(url) == Some(endpoint)),
+            "no request for {:?}", endpoint
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encoded_params_round_trip() -> Result<()> {
+    for (session, url) in record_every_endpoint().await? {
+        let endpoint = Endpoint::from_url(&url).unwrap();
+        let (query, _) = split_signature(&url);
+
+        let decoded: RequestParams = serde_urlencoded::from_str(query).unwrap();
+        assert_eq!(decoded.to_query()?, query, "{:?} does not round-trip", endpoint);
+
+        let pairs: HashMap<String, String> = serde_urlencoded::from_str(query).unwrap();
+        for (key, value) in expected_fields(endpoint) {
+            assert_eq!(pairs.get(*key).map(String::as_str), Some(*value), "{:?}: {}", endpoint, key);
+        }
+        assert_eq!(pairs["aid"], "1988");
+        assert_eq!(pairs["app_name"], "tiktok_web");
+        assert_eq!(pairs["device_platform"], "web_pc");
+        assert_eq!(Some(&pairs["device_id"]), session.device_id.as_ref());
+        assert_eq!(pairs["tz_name"], session.timezone);
+        // The web app sends these empty, so they must not go missing
+        assert_eq!(pairs.get("priority_region").map(String::as_str), Some(""));
+        assert_eq!(pairs.get("referer").map(String::as_str), Some(""));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_query_has_no_json_artifacts() -> Result<()> {
+    for (_, url) in record_every_endpoint().await? {
+        let (query, _) = split_signature(&url);
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap();
+
+        for (key, value) in &pairs {
+            assert!(!value.starts_with('"'), "{} is JSON-quoted in {}", key, url);
+            assert_ne!(value, "null", "{} is null in {}", key, url);
+        }
+        for unset in ["item_id", "web_search_code"] {
+            if Endpoint::from_url(&url) != Some(Endpoint::SearchUser) && Endpoint::from_url(&url) != Some(Endpoint::SearchVideo) {
+                assert!(pairs.iter().all(|(key, _)| key != unset), "unset {} sent in {}", unset, url);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_query_key_order_is_stable() -> Result<()> {
+    let mut params = RequestParams {
+        aid: Some("1988".to_string()),
+        priority_region: Some(String::new()),
+        keyword: Some("a b".to_string()),
+        ..Default::default()
+    };
+    params.additional.insert("zeta".to_string(), "1".to_string());
+    params.additional.insert("alpha".to_string(), "2".to_string());
+    params.additional.insert("empty".to_string(), String::new());
+
+    // Unset fields are left out, while values set to an empty string are still sent
+    assert_eq!(params.to_query()?, "aid=1988&priority_region=&keyword=a+b&alpha=2&empty=&zeta=1");
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::FakeTransport;
+use rust_tok::{
+    api::{CommentApi, VideoApi},
+    endpoints::{Endpoint, EndpointClass},
+    error::Result,
+    rate_limit::{Rate, RateLimitConfig},
+    transport::TransportKind,
+    TikTokApi, TikTokApiConfig,
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+async fn api(rate_limits: RateLimitConfig) -> Result<TikTokApi> {
+    TikTokApi::new(TikTokApiConfig {
+        num_sessions: 2,
+        health_check_interval: None,
+        transport: TransportKind::Custom(Arc::new(FakeTransport::json(serde_json::json!({
+            "itemInfo": { "itemStruct": { "id": "1" } },
+            "comment": { "id": "1", "text": "hi", "diggCount": 0,
+                "author": { "id": "1", "uniqueId": "a", "secUid": "s" } }
+        })))),
+        rate_limits,
+        ..Default::default()
+    }).await
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_global_limit_spaces_requests() -> Result<()> {
+    let api = api(RateLimitConfig {
+        global: Some(Rate::per_second(2)),
+        ..Default::default()
+    }).await?;
+
+    let started = Instant::now();
+    for _ in 0..6 {
+        api.video_info("1").await?;
+    }
+    // Burst of two, then one request every 500ms
+    assert!(started.elapsed() >= Duration::from_secs(2));
+
+    let stats = api.rate_limit_stats().global.unwrap();
+    assert_eq!(stats.acquired, 6);
+    assert_eq!(stats.throttled, 4);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_endpoint_limits_only_apply_to_their_class() -> Result<()> {
+    let api = api(RateLimitConfig {
+        per_endpoint: HashMap::from([(EndpointClass::Comments, Rate::per_minute(1))]),
+        ..Default::default()
+    }).await?;
+
+    let started = Instant::now();
+    for _ in 0..5 {
+        api.video_info("1").await?;
+    }
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    api.comment_info("1").await?;
+    api.comment_info("1").await?;
+    assert!(started.elapsed() >= Duration::from_secs(60));
+
+    let stats = api.rate_limit_stats();
+    assert_eq!(stats.endpoints[&EndpointClass::Comments].acquired, 2);
+    assert!(stats.global.is_none());
+
+    api.close().await?;
+    Ok(())
+}
+
+#[test]
+fn test_endpoint_from_url_ignores_base_path() {
+    assert_eq!(Endpoint::from_url("https://gateway.example.com/tiktok/api/comment/list/?aweme_id=1"), Some(Endpoint::CommentList));
+    assert_eq!(Endpoint::from_url("https://www.tiktok.com/api/comment/list/reply/"), Some(Endpoint::CommentReplies));
+    assert_eq!(Endpoint::from_url("https://www.tiktok.com/api/unknown/"), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_per_session_limit() -> Result<()> {
+    let api = api(RateLimitConfig {
+        per_session: Some(Rate::per_second(1)),
+        ..Default::default()
+    }).await?;
+
+    let started = Instant::now();
+    let requests = (0..4).map(|_| api.video_info("1"));
+    for result in futures::future::join_all(requests).await {
+        result?;
+    }
+    // Two sessions, one request per second each
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    let stats = api.rate_limit_stats();
+    assert_eq!(stats.sessions.values().map(|s| s.acquired).sum::<u64>(), 4);
+
+    api.close().await?;
+    Ok(())
+}
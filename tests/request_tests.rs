@@ -0,0 +1,246 @@
+mod common;
+
+use rust_tok::{
+    error::{Result, TikTokError},
+    request::{Method, RequestBody},
+    signer::{SignRequest, Signer, SignerKind},
+    transport::TransportKind,
+    types::SessionConfig,
+    TikTokApi, TikTokApiConfig,
+};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{body_json, body_string, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Remembers the body it was asked to sign.
+#[derive(Default)]
+struct BodySigner {
+    bodies: Mutex<Vec<Option<Vec<u8>>>>,
+}
+
+#[async_trait::async_trait]
+impl Signer for BodySigner {
+    async fn sign(&self, request: &SignRequest<'_>) -> Result<Vec<(String, String)>> {
+        self.bodies.lock().unwrap().push(request.body.map(<[u8]>::to_vec));
+        Ok(vec![("X-Bogus".to_string(), "signed".to_string())])
+    }
+}
+
+fn api_config(server: &MockServer) -> TikTokApiConfig {
+    TikTokApiConfig {
+        num_sessions: 1,
+        base_url: server.uri(),
+        health_check_interval: None,
+        transport: TransportKind::Http,
+        ..Default::default()
+    }
+}
+
+async fn api(server: &MockServer, signer: SignerKind) -> Result<TikTokApi> {
+    TikTokApi::new(TikTokApiConfig { signer, ..api_config(server) }).await
+}
+
+#[tokio::test]
+async fn test_post_json_body_is_sent_and_signed() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/item/detail/batch/"))
+        .and(header("content-type", "application/json"))
+        .and(body_json(json!({ "itemIds": ["1", "2"] })))
+        .and(query_param("X-Bogus", "signed"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status_code": 0, "items": [] })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let signer = Arc::new(BodySigner::default());
+    let api = api(&server, SignerKind::Custom(signer.clone())).await?;
+    let response = api
+        .request(Method::POST, &format!("{}/api/item/detail/batch/", server.uri()))
+        .json(&json!({ "itemIds": ["1", "2"] }))
+        .send()
+        .await?;
+    assert!(response["items"].is_array());
+
+    let bodies = signer.bodies.lock().unwrap().clone();
+    assert_eq!(bodies, vec![Some(br#"{"itemIds":["1","2"]}"#.to_vec())]);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_form_and_custom_content_type() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/api/report/"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .and(body_string("reason=spam&object_id=42"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status_code": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/raw/"))
+        .and(header("content-type", "text/plain"))
+        .and(body_string("hello"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status_code": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let api = api(&server, SignerKind::Native).await?;
+    api.request(Method::PUT, &format!("{}/api/report/", server.uri()))
+        .form(&[("reason", "spam"), ("object_id", "42")])
+        .send()
+        .await?;
+    api.request(Method::DELETE, &format!("{}/api/raw/", server.uri()))
+        .body(RequestBody::raw("text/plain", "hello"))
+        .send()
+        .await?;
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_without_body_signs_no_body() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/ping/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status_code": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let signer = Arc::new(BodySigner::default());
+    let api = api(&server, SignerKind::Custom(signer.clone())).await?;
+    api.make_request(&format!("{}/api/ping/", server.uri()), None, None, None).await?;
+    assert_eq!(signer.bodies.lock().unwrap().clone(), vec![None]);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_raw_response_exposes_status_headers_and_timing() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/ping/"))
+        .respond_with(ResponseTemplate::new(200)
+            .insert_header("x-tt-logid", "20240101abc")
+            .set_body_string(r#"{"status_code":0}"#))
+        .mount(&server)
+        .await;
+
+    let api = api(&server, SignerKind::Native).await?;
+    let response = api.make_raw_request(&format!("{}/api/ping/", server.uri()), None, None, None).await?;
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("X-TT-LogId"), Some("20240101abc"));
+    assert_eq!(response.session_index, 0);
+    assert!(response.url.starts_with(&format!("{}/api/ping/?X-Bogus=", server.uri())));
+    assert!(response.elapsed > std::time::Duration::ZERO);
+    assert_eq!(response.json()?["status_code"], 0);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_status_is_reported() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/forbidden/"))
+        .respond_with(ResponseTemplate::new(403))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let api = TikTokApi::new(TikTokApiConfig {
+        retry: rust_tok::retry::RetryPolicy::none(),
+        ..api_config(&server)
+    }).await?;
+
+    match api.make_request(&format!("{}/api/forbidden/", server.uri()), None, None, None).await {
+        Err(e @ TikTokError::HttpStatus { status: 403, .. }) => {
+            assert_eq!(e.status(), Some(403));
+            assert!(!e.is_retryable());
+        }
+        other => panic!("Expected HTTP 403, got {:?}", other),
+    }
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_redirect_is_a_captcha() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/item/detail/"))
+        .respond_with(ResponseTemplate::new(302).insert_header("location", "/verify/page?from=api"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/verify/page"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html></html>"))
+        .mount(&server)
+        .await;
+
+    let api = api(&server, SignerKind::Native).await?;
+    let result = api.make_request(&format!("{}/api/item/detail/", server.uri()), None, None, None).await;
+    assert!(matches!(result, Err(TikTokError::CaptchaRequired { .. })), "{:?}", result);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_transport_sends_only_matching_cookies() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/cookies/"))
+        .and(header("cookie", "sessionid=local; msToken=fresh"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status_code": 0 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut local = common::cookie("sessionid", "local", "127.0.0.1");
+    local["secure"] = json!(false);
+    let mut other_path = common::cookie("scoped", "1", "127.0.0.1");
+    other_path["secure"] = json!(false);
+    other_path["path"] = json!("/other/");
+    let mut stale_token = common::cookie("msToken", "stale", "127.0.0.1");
+    stale_token["secure"] = json!(false);
+    let saved = SessionConfig {
+        user_agent: None,
+        language: "en-US".to_string(),
+        platform: "Win32".to_string(),
+        timezone: "UTC".to_string(),
+        screen_width: 1920,
+        screen_height: 1080,
+        ms_token: Some("fresh".to_string()),
+        proxy: None,
+        cookies: Some(serde_json::from_value(json!([
+            local,
+            other_path,
+            stale_token,
+            // Another site's cookie, and one only sent over https
+            common::cookie("sessionid", "elsewhere", ".tiktok.com"),
+            common::cookie("secure", "1", "127.0.0.1"),
+        ]))?),
+        device_id: None,
+    };
+    let file = std::env::temp_dir().join(format!("rust-tok-http-cookies-{}.json", std::process::id()));
+    std::fs::write(&file, serde_json::to_vec(&vec![saved])?).unwrap();
+    let api = TikTokApi::from_saved_sessions(api_config(&server), &file).await?;
+    std::fs::remove_file(&file).ok();
+
+    api.request(Method::GET, &format!("{}/api/cookies/", server.uri())).send().await?;
+
+    api.close().await?;
+    Ok(())
+}
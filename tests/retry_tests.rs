@@ -0,0 +1,101 @@
+mod common;
+
+use common::FakeTransport;
+use rust_tok::{
+    api::UserApi,
+    error::{ApiStatus, Result, TikTokError},
+    retry::RetryPolicy,
+    transport::TransportKind,
+    TikTokApi, TikTokApiConfig,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Fails with the given errors in order, then answers a one-page `user_videos` response.
+fn scripted(errors: Vec<TikTokError>) -> Arc<FakeTransport> {
+    let page = serde_json::json!({ "itemList": [{ "id": "1" }, { "id": "2" }], "hasMore": false });
+    Arc::new(FakeTransport::json(page).failing_with(errors))
+}
+
+fn config(transport: Arc<FakeTransport>, retry: RetryPolicy) -> TikTokApiConfig {
+    TikTokApiConfig {
+        num_sessions: 2,
+        transport: TransportKind::Custom(transport),
+        retry,
+        ..Default::default()
+    }
+}
+
+fn fast_retries(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        base_delay: Duration::from_millis(1),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_paginated_call_retries_transient_errors() -> Result<()> {
+    let transport = scripted(vec![
+        TikTokError::EmptyResponse,
+        TikTokError::ApiError("TikTok API error: Status code 10201".to_string()),
+    ]);
+    let api = TikTokApi::new(config(transport.clone(), fast_retries(3))).await?;
+
+    let videos = api.user_videos("sec_uid", 2).await?;
+    assert_eq!(videos.len(), 2);
+    assert_eq!(transport.calls(), 3);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gives_up_after_max_retries() -> Result<()> {
+    let transport = scripted(vec![
+        TikTokError::EmptyResponse,
+        TikTokError::EmptyResponse,
+        TikTokError::EmptyResponse,
+    ]);
+    let api = TikTokApi::new(config(transport.clone(), fast_retries(1))).await?;
+
+    assert!(matches!(api.user_videos("sec_uid", 2).await, Err(TikTokError::EmptyResponse)));
+    assert_eq!(transport.calls(), 2);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fatal_errors_are_not_retried() -> Result<()> {
+    let missing = ApiStatus { code: 10202, message: None, endpoint: "/api/post/item_list/".to_string(), log_id: None };
+    let transport = scripted(vec![TikTokError::NotFound(missing)]);
+    let api = TikTokApi::new(config(transport.clone(), fast_retries(3))).await?;
+
+    assert!(matches!(api.user_videos("sec_uid", 2).await, Err(TikTokError::NotFound(_))));
+    assert_eq!(transport.calls(), 1);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[test]
+fn test_backoff_is_exponential_and_capped() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(350),
+        jitter: 0.0,
+        ..Default::default()
+    };
+
+    assert_eq!(policy.delay(1), Duration::from_millis(100));
+    assert_eq!(policy.delay(2), Duration::from_millis(200));
+    assert_eq!(policy.delay(3), Duration::from_millis(350));
+
+    let jittered = RetryPolicy { jitter: 0.5, ..policy };
+    for attempt in 1..5 {
+        let delay = jittered.delay(attempt);
+        assert!(delay <= Duration::from_millis(350));
+        assert!(delay >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,458 @@
+mod common;
+
+use common::{Behaviour, FakeChrome, FakeTransport, HEADLESS_USER_AGENT};
+use rust_tok::{
+    api::VideoApi,
+    error::{Result, TikTokError},
+    fingerprint::FingerprintProfile,
+    retry::RetryPolicy,
+    signer::SignerKind,
+    supervisor::SessionEventKind,
+    transport::TransportKind,
+    types::{RequestParams, SessionConfig},
+    TikTokApi, TikTokApiConfig,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Answers every request with a minimal `video_info` response.
+fn item_transport() -> FakeTransport {
+    FakeTransport::json(json!({ "itemInfo": { "itemStruct": { "id": "1" } } }))
+}
+
+#[tokio::test]
+async fn test_sessions_serve_requests_in_parallel() -> Result<()> {
+    let transport = Arc::new(item_transport().delay(Duration::from_millis(50)));
+    let config = TikTokApiConfig {
+        num_sessions: 4,
+        transport: TransportKind::Custom(transport.clone()),
+        ..Default::default()
+    };
+    let api = TikTokApi::new(config).await?;
+
+    let requests = (0..12).map(|_| api.video_info("1"));
+    for result in futures::future::join_all(requests).await {
+        result?;
+    }
+
+    // Never more requests in flight than sessions, and every session is used at once
+    assert_eq!(transport.max_in_flight(), 4);
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_session_index() -> Result<()> {
+    let config = TikTokApiConfig {
+        num_sessions: 1,
+        transport: TransportKind::Custom(Arc::new(item_transport())),
+        ..Default::default()
+    };
+    let api = TikTokApi::new(config).await?;
+
+    assert!(api.make_request("https://www.tiktok.com/api/item/detail/", None, None, Some(3)).await.is_err());
+    api.make_request("https://www.tiktok.com/api/item/detail/", None, None, Some(0)).await?;
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failing_session_is_quarantined_and_replaced() -> Result<()> {
+    let config = TikTokApiConfig {
+        num_sessions: 1,
+        max_session_failures: 2,
+        health_check_interval: None,
+        retry: RetryPolicy::none(),
+        transport: TransportKind::Custom(Arc::new(item_transport().failing_with(vec![
+            TikTokError::BrowserError("Target closed".to_string()),
+            TikTokError::BrowserError("Target closed".to_string()),
+        ]))),
+        ..Default::default()
+    };
+    let api = TikTokApi::new(config).await?;
+
+    assert!(api.video_info("1").await.is_err());
+    assert!(api.video_info("1").await.is_err());
+    // The only session is quarantined, so nothing can be leased
+    assert!(api.video_info("1").await.is_err());
+
+    api.check_sessions().await?;
+    api.video_info("1").await?;
+
+    let events = api.session_events();
+    assert!(matches!(events[0].kind, SessionEventKind::Quarantined { .. }));
+    assert!(matches!(events[1].kind, SessionEventKind::Replaced { .. }));
+    assert!(events.iter().all(|event| event.session_index == 0));
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sessions_survive_save_and_restore() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("rust-tok-sessions-{}.json", std::process::id()));
+    let config = TikTokApiConfig {
+        num_sessions: 2,
+        health_check_interval: None,
+        ms_tokens: Some(vec!["saved_token".to_string()]),
+        transport: TransportKind::Custom(Arc::new(item_transport())),
+        ..Default::default()
+    };
+
+    let api = TikTokApi::new(config.clone()).await?;
+    let exported = api.export_sessions().await?;
+    assert_eq!(exported.len(), 2);
+    assert!(exported.iter().all(|s| s.device_id.as_ref().is_some_and(|id| id.len() == 19)));
+    api.save_sessions(&path).await?;
+    api.close().await?;
+
+    let restored = TikTokApi::from_saved_sessions(TikTokApiConfig { num_sessions: 3, ..config }, &path).await?;
+    let sessions = restored.export_sessions().await?;
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(sessions.len(), 3);
+    for (saved, restored) in exported.iter().zip(&sessions) {
+        assert_eq!(saved.device_id, restored.device_id);
+        assert_eq!(restored.ms_token.as_deref(), Some("saved_token"));
+    }
+    assert!(!exported.iter().any(|s| s.device_id == sessions[2].device_id));
+
+    restored.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fingerprint_profile_fills_session_and_params() -> Result<()> {
+    let profile = FingerprintProfile {
+        user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) Test/1.0".to_string(),
+        platform: "MacIntel".to_string(),
+        language: "en-GB".to_string(),
+        timezone: "Europe/London".to_string(),
+        screen_width: 1680,
+        screen_height: 1050,
+    };
+    let transport = Arc::new(item_transport());
+    let api = TikTokApi::new(TikTokApiConfig {
+        num_sessions: 1,
+        transport: TransportKind::Custom(transport.clone()),
+        fingerprints: Some(vec![profile.clone()]),
+        ..Default::default()
+    }).await?;
+
+    api.video_info("1").await?;
+
+    let sent = transport.sent().remove(0);
+    let (session, url) = (sent.session, sent.request.url);
+    assert_eq!(session.user_agent.as_deref(), Some(profile.user_agent.as_str()));
+    assert_eq!(session.timezone, "Europe/London");
+    assert_eq!((session.screen_width, session.screen_height), (1680, 1050));
+
+    let query: std::collections::HashMap<String, String> = url::Url::parse(&url).unwrap()
+        .query_pairs()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    assert_eq!(query["tz_name"], "Europe/London");
+    assert_eq!(query["browser_language"], "en-GB");
+    assert_eq!(query["browser_platform"], "MacIntel");
+    assert_eq!(query["screen_width"], "1680");
+    assert_eq!(query["browser_version"], "5.0 (Macintosh; Intel Mac OS X 10_15_7) Test/1.0");
+
+    api.close().await?;
+    Ok(())
+}
+
+#[test]
+fn test_request_params_merge_over_session_defaults() {
+    let session = SessionConfig {
+        user_agent: None,
+        language: "en-GB".to_string(),
+        platform: "MacIntel".to_string(),
+        timezone: "Europe/London".to_string(),
+        screen_width: 1440,
+        screen_height: 900,
+        ms_token: None,
+        proxy: None,
+        cookies: None,
+        device_id: Some("7300000000000000001".to_string()),
+    };
+
+    let params = RequestParams {
+        aweme_id: Some("42".to_string()),
+        from_page: Some("video".to_string()),
+        ..Default::default()
+    }.merged_over(RequestParams::for_session(&session));
+
+    assert_eq!(params.aid.as_deref(), Some("1988"));
+    assert_eq!(params.app_name.as_deref(), Some("tiktok_web"));
+    assert_eq!(params.device_platform.as_deref(), Some("web_pc"));
+    assert_eq!(params.device_id.as_deref(), Some("7300000000000000001"));
+    assert_eq!(params.region.as_deref(), Some("GB"));
+    assert_eq!(params.app_language.as_deref(), Some("en"));
+    assert_eq!(params.os.as_deref(), Some("mac"));
+    assert_eq!(params.screen_width.as_deref(), Some("1440"));
+    assert_eq!(params.tz_name.as_deref(), Some("Europe/London"));
+    assert_eq!(params.from_page.as_deref(), Some("video"));
+    assert_eq!(params.aweme_id.as_deref(), Some("42"));
+}
+
+#[tokio::test]
+async fn test_connect_reports_unreachable_browser() {
+    // Nothing listens on port 1; connecting must fail rather than fall back to launching Chrome
+    let config = TikTokApiConfig {
+        num_sessions: 1,
+        health_check_interval: None,
+        connect: Some(vec!["ws://127.0.0.1:1/devtools/browser/none".to_string()]),
+        ..Default::default()
+    };
+    match TikTokApi::new(config).await {
+        Err(TikTokError::BrowserError(message)) => assert!(message.contains("cannot connect"), "{}", message),
+        Err(e) => panic!("Expected a connection error, got {}", e),
+        Ok(_) => panic!("Connected to a browser that does not exist"),
+    }
+}
+
+#[tokio::test]
+async fn test_close_twice_and_drop_without_close() -> Result<()> {
+    let config = || TikTokApiConfig {
+        num_sessions: 2,
+        transport: TransportKind::Custom(Arc::new(item_transport())),
+        ..Default::default()
+    };
+
+    let api = TikTokApi::new(config()).await?;
+    api.close().await?;
+    api.close().await?;
+
+    // Dropped with the supervisor still running and sessions still in the pool
+    drop(TikTokApi::new(config()).await?);
+    Ok(())
+}
+
+fn fake_chrome_config(chrome: &FakeChrome, num_sessions: usize) -> TikTokApiConfig {
+    let mut config = TikTokApiConfig {
+        num_sessions,
+        health_check_interval: None,
+        warmup_timeout: Duration::from_millis(100),
+        ..Default::default()
+    };
+    config.launch.executable = Some(chrome.executable());
+    config
+}
+
+#[tokio::test]
+async fn test_stuck_browser_is_killed_on_close() -> Result<()> {
+    let chrome = FakeChrome::start(Behaviour { hang: vec!["Browser.close"], ..Default::default() }).await;
+    let api = TikTokApi::new(fake_chrome_config(&chrome, 1)).await?;
+    let launched = chrome.launched();
+    assert_eq!(launched.len(), 1);
+
+    api.close().await?;
+    assert_eq!(chrome.calls("Browser.close").len(), 1);
+    assert!(!common::is_running(launched[0]), "browser {} survived close", launched[0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shared_browser_closes_with_its_last_session() -> Result<()> {
+    let chrome = FakeChrome::start(Behaviour::default()).await;
+    let mut config = fake_chrome_config(&chrome, 3);
+    config.launch.sessions_per_browser = 2;
+    let api = TikTokApi::new(config).await?;
+
+    // Three sessions fit in two browsers, each session in a context of its own
+    let launched = chrome.launched();
+    assert_eq!(launched.len(), 2);
+    assert_eq!(chrome.calls("Target.createBrowserContext").len(), 3);
+
+    api.close().await?;
+    assert_eq!(chrome.calls("Target.disposeBrowserContext").len(), 3);
+    assert_eq!(chrome.calls("Browser.close").len(), 2);
+    for pid in launched {
+        assert!(!common::is_running(pid), "browser {} survived close", pid);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_reports_every_failed_session() -> Result<()> {
+    let chrome = FakeChrome::start(Behaviour { fail: vec!["Target.disposeBrowserContext"], ..Default::default() }).await;
+    let api = TikTokApi::new(TikTokApiConfig {
+        num_sessions: 2,
+        connect: Some(vec![chrome.url.clone()]),
+        health_check_interval: None,
+        warmup_timeout: Duration::from_millis(100),
+        ..Default::default()
+    }).await?;
+
+    match api.close().await {
+        Err(TikTokError::CloseFailed(errors)) => {
+            let indices: Vec<_> = errors.iter().map(|(index, _)| *index).collect();
+            assert_eq!(indices, vec![0, 1]);
+            let message = TikTokError::CloseFailed(errors).to_string();
+            assert!(message.contains("session 0: ") && message.contains("; session 1: "), "{}", message);
+        }
+        other => panic!("Expected CloseFailed, got {:?}", other),
+    }
+    // Both pages were still closed, and the connected browser was left running
+    assert_eq!(chrome.calls("Page.close").len(), 2);
+    assert!(chrome.calls("Browser.close").is_empty());
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crashed_shared_browser_is_replaced() -> Result<()> {
+    let chrome = FakeChrome::start(Behaviour::default()).await;
+    let mut config = fake_chrome_config(&chrome, 2);
+    config.launch.sessions_per_browser = 3;
+    let api = TikTokApi::new(config).await?;
+    assert_eq!(chrome.launched().len(), 1);
+
+    // The crashed browser still has room for a session, but respawns must not be opened in it
+    chrome.crash();
+    api.check_sessions().await?;
+    let events = api.session_events();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| matches!(event.kind, SessionEventKind::Replaced { .. })), "{:?}", events);
+    let launched = chrome.launched();
+    assert_eq!(launched.len(), 2);
+
+    // Both replacements share the new browser
+    api.close().await?;
+    assert_eq!(chrome.calls("Browser.close").len(), 1);
+    assert!(!common::is_running(launched[1]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_warm_up_sets_and_harvests_ms_token() -> Result<()> {
+    let chrome = FakeChrome::start(Behaviour {
+        results: HashMap::from([("Network.getCookies", json!({ "cookies": [
+            common::cookie("msToken", "harvested", ".tiktok.com"),
+            common::cookie("ttwid", "1%7Cabc", ".tiktok.com"),
+        ] }))]),
+        ..Default::default()
+    }).await;
+    let transport = Arc::new(item_transport().needs_browser());
+    let mut config = fake_chrome_config(&chrome, 1);
+    config.ms_tokens = Some(vec!["configured".to_string()]);
+    config.transport = TransportKind::Custom(transport.clone());
+    config.signer = SignerKind::Native;
+    let api = TikTokApi::new(config).await?;
+
+    // The configured token is in the jar before TikTok first loads
+    let commands = chrome.commands.lock().unwrap().clone();
+    let set_token = commands.iter().position(|command| {
+        command.method == "Network.setCookies"
+            && command.params["cookies"].as_array().unwrap().iter().any(|cookie| cookie["name"] == "msToken" && cookie["value"] == "configured")
+    });
+    let navigate = commands.iter().position(|command| command.method == "Page.navigate");
+    assert!(set_token.is_some() && set_token < navigate, "{:?}", commands);
+
+    // The session then goes out with the token TikTok handed back
+    api.video_info("1").await?;
+    let session = transport.sent().remove(0).session;
+    assert_eq!(session.ms_token.as_deref(), Some("harvested"));
+    assert!(session.cookies.unwrap().iter().any(|cookie| cookie.name == "ttwid" && cookie.value == "1%7Cabc"));
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_warm_up_fills_missing_user_agent() -> Result<()> {
+    let chrome = FakeChrome::start(Behaviour::default()).await;
+    let path = std::env::temp_dir().join(format!("rust-tok-no-user-agent-{}.json", std::process::id()));
+    let saved = SessionConfig {
+        user_agent: None,
+        language: "en-US".to_string(),
+        platform: "Linux x86_64".to_string(),
+        timezone: "UTC".to_string(),
+        screen_width: 1920,
+        screen_height: 1080,
+        ms_token: None,
+        proxy: None,
+        cookies: None,
+        device_id: None,
+    };
+    std::fs::write(&path, serde_json::to_vec(&vec![saved])?).unwrap();
+
+    let config = TikTokApiConfig { stealth: false, ..fake_chrome_config(&chrome, 1) };
+    let api = TikTokApi::from_saved_sessions(config, &path).await?;
+    std::fs::remove_file(&path).ok();
+
+    // Without stealth nothing emulates a user agent, so the browser's own is recorded
+    let sessions = api.export_sessions().await?;
+    assert_eq!(sessions[0].user_agent.as_deref(), Some(HEADLESS_USER_AGENT));
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_without_signing_sdk_is_not_ready() -> Result<()> {
+    let chrome = FakeChrome::start(Behaviour {
+        results: HashMap::from([("Runtime.evaluate", json!({ "result": { "type": "boolean", "value": false } }))]),
+        ..Default::default()
+    }).await;
+
+    match TikTokApi::new(fake_chrome_config(&chrome, 1)).await {
+        Err(TikTokError::BrowserError(message)) => assert!(message.contains("signing SDK"), "{}", message),
+        Err(e) => panic!("Expected BrowserError, got {:?}", e),
+        Ok(_) => panic!("A session without the signing SDK joined the pool"),
+    }
+    // The SDK was polled until the timeout, and the browser did not outlive the failed session
+    assert!(chrome.calls("Runtime.evaluate").len() > 1);
+    let launched = chrome.launched();
+    assert_eq!(launched.len(), 1);
+    assert!(!common::is_running(launched[0]), "browser {} survived", launched[0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_saved_cookies_keep_their_domain_and_flags() -> Result<()> {
+    let mut persistent = common::cookie("sessionid", "main", ".tiktok.com");
+    persistent["httpOnly"] = json!(true);
+    persistent["session"] = json!(false);
+    persistent["expires"] = json!(1900000000.5);
+    let chrome = FakeChrome::start(Behaviour {
+        results: HashMap::from([("Network.getCookies", json!({ "cookies": [
+            persistent,
+            common::cookie("sessionid", "other", ".tiktokv.com"),
+        ] }))]),
+        ..Default::default()
+    }).await;
+    let path = std::env::temp_dir().join(format!("rust-tok-cookies-{}.json", std::process::id()));
+    let api = TikTokApi::new(fake_chrome_config(&chrome, 1)).await?;
+    api.save_sessions(&path).await?;
+    api.close().await?;
+
+    let fresh = FakeChrome::start(Behaviour::default()).await;
+    let restored = TikTokApi::from_saved_sessions(fake_chrome_config(&fresh, 1), &path).await?;
+    std::fs::remove_file(&path).ok();
+
+    // Same-named cookies of different domains both come back, each where it was set
+    let calls = fresh.calls("Network.setCookies");
+    let cookies = calls[0].params["cookies"].as_array().unwrap();
+    assert_eq!(cookies.len(), 2, "{:?}", cookies);
+    let main = cookies.iter().find(|cookie| cookie["domain"] == ".tiktok.com").unwrap();
+    assert_eq!(main["name"], "sessionid");
+    assert_eq!(main["value"], "main");
+    assert_eq!(main["path"], "/");
+    assert_eq!(main["secure"], true);
+    assert_eq!(main["httpOnly"], true);
+    assert_eq!(main["expires"], 1900000000.5);
+    assert!(main.get("url").is_none());
+    let other = cookies.iter().find(|cookie| cookie["domain"] == ".tiktokv.com").unwrap();
+    assert_eq!(other["value"], "other");
+    // A session cookie stays one
+    assert!(other.get("expires").is_none());
+
+    restored.close().await?;
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use rust_tok::{
+    api::UserApi,
+    error::Result,
+    fingerprint::FingerprintProfile,
+    signer::{xbogus::x_bogus, SignRequest, Signer, SignerKind},
+    transport::TransportKind,
+    TikTokApi, TikTokApiConfig,
+};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{body_partial_json, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+const ALPHABET: &[u8; 64] = b"Dkdpgh4ZKsQB80/Mfvw36XI1R25-WUAlEi7NLboqYTOPuzmFjJnryx9HVGcaStCe";
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut s: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+        s.swap(i, j as usize);
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(s[i as usize]);
+            s.swap(i as usize, j as usize);
+            byte ^ s[s[i as usize].wrapping_add(s[j as usize]) as usize]
+        })
+        .collect()
+}
+
+/// The 19 values an X-Bogus encodes: header, hash bytes, timestamp, canvas and checksum.
+fn decode(x_bogus: &str) -> Vec<u8> {
+    let sextets: Vec<u32> = x_bogus.bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c).expect("X-Bogus alphabet") as u32)
+        .collect();
+    let bytes: Vec<u8> = sextets
+        .chunks(4)
+        .flat_map(|c| {
+            let n = c[0] << 18 | c[1] << 12 | c[2] << 6 | c[3];
+            [(n >> 16) as u8, (n >> 8) as u8, n as u8]
+        })
+        .collect();
+    assert_eq!(bytes[..2], [2, 255]);
+    rc4(&[255], &bytes[2..])
+}
+
+fn timestamp(values: &[u8]) -> u32 {
+    u32::from_be_bytes(values[10..14].try_into().unwrap())
+}
+
+/// Query, user agent, timestamp and the X-Bogus expected for them. These are regression vectors
+/// pinned from this implementation; `test_x_bogus_matches_browser_sdk` prints vectors captured from
+/// TikTok's own `frontierSign` in the same layout.
+const VECTORS: [(&str, &str, u32, &str); 3] = [
+    (
+        "aweme_id=7160347587318287657&aid=1128&version_name=23.5.0&device_platform=android&os_version=2333",
+        USER_AGENT,
+        1700000000,
+        "DFSzswVYJ6kANG//tmWx-e9WX7rZ",
+    ),
+    (
+        "aid=1988&app_name=tiktok_web&device_platform=web_pc&unique_id=therock",
+        USER_AGENT,
+        1712345678,
+        "DFSzswVYFOGANG//t5wIKl9WX7re",
+    ),
+    ("", USER_AGENT, 1600000000, "DFSzswVY0IJANG//r/P6-e9WX7r0"),
+];
+
+#[test]
+fn test_x_bogus_known_answers() {
+    for (query, user_agent, timestamp, expected) in VECTORS {
+        assert_eq!(x_bogus(query, user_agent, None, timestamp), expected, "query: {}", query);
+        assert_eq!(self::timestamp(&decode(expected)), timestamp);
+    }
+}
+
+#[test]
+fn test_x_bogus_covers_body() {
+    let query = "aid=1988&app_name=tiktok_web";
+    let signed = x_bogus(query, USER_AGENT, Some(br#"{"itemIds":["1"]}"#), 1712345678);
+    assert_eq!(signed, "DFSzswVYlXKb3G//t5wIKl9WX7Ja");
+    assert_ne!(signed, x_bogus(query, USER_AGENT, None, 1712345678));
+}
+
+/// Signs with `frontierSign` in the session's page and keeps the query, user agent and X-Bogus.
+#[derive(Default)]
+struct CapturingSigner {
+    captured: Mutex<Vec<(String, String, String)>>,
+}
+
+#[async_trait::async_trait]
+impl Signer for CapturingSigner {
+    async fn sign(&self, request: &SignRequest<'_>) -> Result<Vec<(String, String)>> {
+        let page = request.session.page.as_ref().expect("browser session");
+        let script = format!(
+            r#"() => {{
+                const signed = window.byted_acrawler.frontierSign({});
+                const xBogus = typeof signed === 'string' ? JSON.parse(signed)['X-Bogus'] : signed['X-Bogus'];
+                return [navigator.userAgent, xBogus];
+            }}"#,
+            serde_json::to_string(request.url)?
+        );
+        let (user_agent, x_bogus): (String, String) = page.evaluate(script).await?.into_value()?;
+
+        let query = url::Url::parse(request.url)?.query().unwrap_or_default().to_string();
+        self.captured.lock().unwrap().push((query, user_agent, x_bogus.clone()));
+        Ok(vec![("X-Bogus".to_string(), x_bogus)])
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs Chrome and network access to tiktok.com"]
+async fn test_x_bogus_matches_browser_sdk() -> Result<()> {
+    let signer = Arc::new(CapturingSigner::default());
+    let api = TikTokApi::new(TikTokApiConfig {
+        num_sessions: 1,
+        signer: SignerKind::Custom(signer.clone()),
+        ..Default::default()
+    }).await?;
+
+    // Only the signature matters, not whether TikTok answers
+    let _ = api.user_info("therock").await;
+    let captured = signer.captured.lock().unwrap().clone();
+    assert!(!captured.is_empty());
+
+    for (query, user_agent, captured) in captured {
+        let browser = decode(&captured);
+        // In the layout of `VECTORS`, to pin as known answers
+        println!("({:?}, {:?}, {}, {:?}),", query, user_agent, timestamp(&browser), captured);
+        let native = decode(&x_bogus(&query, &user_agent, None, timestamp(&browser)));
+        // The canvas fingerprint differs per browser, and the checksum covers it
+        assert_eq!(native[..14], browser[..14], "X-Bogus {} for {}", captured, query);
+    }
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remote_signer() -> Result<()> {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/sign"))
+        .and(body_partial_json(json!({ "user_agent": USER_AGENT })))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_json(json!({ "params": { "X-Bogus": "DFSzswVYremote" } })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/user/detail/"))
+        .and(query_param("X-Bogus", "DFSzswVYremote"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_json(json!({
+                "userInfo": { "user": { "id": "1", "uniqueId": "signed", "secUid": "sec1" } }
+            })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = TikTokApiConfig {
+        num_sessions: 1,
+        base_url: server.uri(),
+        transport: TransportKind::Http,
+        signer: SignerKind::Remote(format!("{}/sign", server.uri())),
+        fingerprints: Some(FingerprintProfile::builtin()[..1].to_vec()),
+        ..Default::default()
+    };
+
+    let api = TikTokApi::new(config).await?;
+    let user = api.user_info("signed").await?;
+    assert_eq!(user.username, "signed");
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_closure_signer() -> Result<()> {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/user/detail/"))
+        .and(query_param("X-Bogus", "closure"))
+        .and(query_param("_signature", "a+b/c=&d"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_json(json!({
+                "userInfo": { "user": { "id": "1", "uniqueId": "signed", "secUid": "sec1" } }
+            })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = TikTokApiConfig {
+        num_sessions: 1,
+        base_url: server.uri(),
+        transport: TransportKind::Http,
+        signer: SignerKind::from_fn(|url, _user_agent| {
+            assert!(url.contains("unique_id"));
+            // Values are encoded, so their own `+`, `=` and `&` survive
+            Ok(vec![
+                ("X-Bogus".to_string(), "closure".to_string()),
+                ("_signature".to_string(), "a+b/c=&d".to_string()),
+            ])
+        }),
+        ..Default::default()
+    };
+
+    let api = TikTokApi::new(config).await?;
+    api.user_info("signed").await?;
+
+    api.close().await?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::FakeTransport;
+use rust_tok::{
+    api::{UserApi, VideoApi},
+    error::{Result, TikTokError},
+    retry::RetryPolicy,
+    transport::TransportKind,
+    TikTokApi, TikTokApiConfig,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+async fn api(body: serde_json::Value) -> Result<TikTokApi> {
+    TikTokApi::new(TikTokApiConfig {
+        num_sessions: 1,
+        health_check_interval: None,
+        retry: RetryPolicy::none(),
+        transport: TransportKind::Custom(Arc::new(FakeTransport::json(body))),
+        ..Default::default()
+    }).await
+}
+
+#[tokio::test]
+async fn test_missing_user_is_not_found() -> Result<()> {
+    let api = api(json!({ "userInfo": {}, "statusCode": 10202, "statusMsg": "" })).await?;
+    match api.user_info("nonexistent").await {
+        Err(TikTokError::NotFound(status)) => {
+            assert_eq!(status.code, 10202);
+            assert_eq!(status.endpoint, "/api/user/detail/");
+        }
+        other => panic!("Expected NotFound, got {:?}", other.map(|user| user.username)),
+    }
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deleted_video_keeps_status_details() -> Result<()> {
+    let api = api(json!({
+        "statusCode": 10204,
+        "statusMsg": "item doesn't exist",
+        "log_pb": { "impr_id": "2024010112000001" }
+    })).await?;
+
+    match api.video_info("1").await {
+        Err(e @ TikTokError::VideoUnavailable(_)) => {
+            let status = e.api_status().unwrap();
+            assert_eq!(status.code, 10204);
+            assert_eq!(status.message.as_deref(), Some("item doesn't exist"));
+            assert_eq!(status.endpoint, "/api/item/detail/");
+            assert_eq!(status.log_id.as_deref(), Some("2024010112000001"));
+        }
+        other => panic!("Expected VideoUnavailable, got {:?}", other.map(|v| v.id)),
+    }
+
+    api.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_status_codes_map_to_variants() -> Result<()> {
+    let cases = [
+        (10222, "PrivateAccount"),
+        (10213, "RegionRestricted"),
+        (10114, "RateLimited"),
+        (10102, "LoginRequired"),
+        (5, "InvalidParameters"),
+        (12345, "ApiError"),
+    ];
+
+    for (code, expected) in cases {
+        let api = api(json!({ "status_code": code, "status_msg": "nope" })).await?;
+        let error = api.user_info("someone").await.unwrap_err();
+        assert!(format!("{:?}", error).starts_with(expected), "{} mapped to {:?}", code, error);
+        api.close().await?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_status_classification() {
+    let status = |code| rust_tok::error::ApiStatus {
+        code,
+        message: None,
+        endpoint: "/api/post/item_list/".to_string(),
+        log_id: None,
+    };
+
+    let throttled = TikTokError::RateLimited(status(10114));
+    assert!(throttled.is_retryable());
+    assert!(throttled.is_session_failure());
+
+    let private = TikTokError::PrivateAccount(status(10222));
+    assert!(!private.is_retryable());
+    assert!(!private.is_session_failure());
+}
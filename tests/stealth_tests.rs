@@ -0,0 +1,176 @@
+mod common;
+
+use common::{Behaviour, FakeChrome, HEADLESS_USER_AGENT};
+use rust_tok::{fingerprint::FingerprintProfile, stealth, types::SessionConfig, TikTokApi, TikTokApiConfig};
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+fn session(platform: &str, device_id: &str) -> SessionConfig {
+    SessionConfig {
+        user_agent: None,
+        language: "en-GB".to_string(),
+        platform: platform.to_string(),
+        timezone: "Europe/London".to_string(),
+        screen_width: 1680,
+        screen_height: 1050,
+        ms_token: None,
+        proxy: None,
+        cookies: None,
+        device_id: Some(device_id.to_string()),
+    }
+}
+
+#[test]
+fn test_webgl_matches_platform_and_device() {
+    for device_id in ["7000000000000000001", "7000000000000000002", "7000000000000000003"] {
+        let (vendor, renderer) = stealth::webgl_identity(&session("Win32", device_id));
+        assert!(vendor.starts_with("Google Inc."), "{}", vendor);
+        assert!(renderer.contains("Direct3D11"), "{}", renderer);
+
+        // Mac user agents and client hints claim an Intel CPU, so the GPU must not be Apple silicon
+        let (vendor, renderer) = stealth::webgl_identity(&session("MacIntel", device_id));
+        assert!(vendor.contains("Intel") && renderer.contains("OpenGL 4.1"), "{} / {}", vendor, renderer);
+        assert!(stealth::webgl_identity(&session("Linux x86_64", device_id)).1.contains("OpenGL 4.6"));
+    }
+
+    // The same session always presents the same GPU
+    let mac = session("MacIntel", "7123456789012345678");
+    assert_eq!(stealth::webgl_identity(&mac), stealth::webgl_identity(&mac.clone()));
+}
+
+#[test]
+fn test_script_carries_the_fingerprint() {
+    let mut mac = session("MacIntel", "7123456789012345678");
+    mac.platform = "Mac\"Intel".to_string();
+    let script = stealth::script(&mac);
+    let (_, renderer) = stealth::webgl_identity(&mac);
+
+    assert!(script.contains("'webdriver', false"));
+    assert!(script.contains(r#""languages":["en-GB","en"]"#));
+    assert!(script.contains(&serde_json::to_string(renderer).unwrap()));
+    // Values are embedded as JSON, never spliced into the code
+    assert!(script.contains(r#""platform":"Mac\"Intel""#));
+}
+
+#[test]
+fn test_stealth_is_on_by_default() -> rust_tok::error::Result<()> {
+    assert!(TikTokApiConfig::default().stealth);
+    assert!(!TikTokApiConfig::builder().stealth(false).build()?.stealth);
+    Ok(())
+}
+
+/// Just enough of a page for the evasions to install themselves: the DOM classes they patch and a
+/// headless navigator without plugins.
+const PAGE_STUBS: &str = r#"
+class Navigator {}
+class PluginArray {}
+class MimeTypeArray {}
+class PermissionStatus {}
+class Permissions { query() { return Promise.resolve({ state: 'granted' }); } }
+class WebGLRenderingContext { getParameter() { return 'SwiftShader'; } }
+Object.defineProperty(Navigator.prototype, 'plugins', { get: () => [], configurable: true });
+Object.assign(globalThis, { Navigator, PluginArray, MimeTypeArray, PermissionStatus, Permissions, WebGLRenderingContext });
+globalThis.window = globalThis;
+globalThis.Notification = { permission: 'default' };
+Object.defineProperty(globalThis, 'navigator', { value: new Navigator(), configurable: true });
+window.outerWidth = 0;
+window.outerHeight = 0;
+"#;
+
+const PAGE_PROBE: &str = r#"
+const plugins = navigator.plugins;
+console.log(JSON.stringify({
+    named: plugins.namedItem('Chrome PDF Viewer').name,
+    missing: plugins.namedItem('Flash'),
+    item: plugins.item(0).name,
+    length: plugins.length,
+    pluginArray: plugins instanceof PluginArray,
+    namedItemSource: plugins.namedItem.toString(),
+    mimeType: navigator.mimeTypes.namedItem('application/pdf').suffixes,
+    webdriver: navigator.webdriver,
+    platform: navigator.platform,
+    renderer: new WebGLRenderingContext().getParameter(37446),
+    outerHeight: window.outerHeight,
+}));
+"#;
+
+#[test]
+fn test_script_runs_in_a_page() {
+    let session = session("Win32", "7123456789012345678");
+    let mut node = Command::new("node")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("node must be installed to evaluate the stealth script");
+    let program = format!("{}\n{}\n{}", PAGE_STUBS, stealth::script(&session), PAGE_PROBE);
+    node.stdin.take().unwrap().write_all(program.as_bytes()).unwrap();
+    let output = node.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let page: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(page["named"], "Chrome PDF Viewer");
+    assert_eq!(page["missing"], Value::Null);
+    assert_eq!(page["item"], "PDF Viewer");
+    assert_eq!(page["length"], 5);
+    assert_eq!(page["pluginArray"], true);
+    assert_eq!(page["namedItemSource"], "function namedItem() { [native code] }");
+    assert_eq!(page["mimeType"], "pdf");
+    assert_eq!(page["webdriver"], false);
+    assert_eq!(page["platform"], "Win32");
+    assert_eq!(page["renderer"], stealth::webgl_identity(&session).1);
+    assert_eq!(page["outerHeight"], 1050 + 85);
+}
+
+#[test]
+fn test_user_agent_metadata_matches_fingerprint() {
+    let mut mac = session("MacIntel", "7123456789012345678");
+    mac.user_agent = Some(
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.6045.105 Safari/537.36".to_string()
+    );
+    let metadata = stealth::user_agent_metadata(&mac).unwrap();
+    assert_eq!((metadata.platform.as_str(), metadata.platform_version.as_str()), ("macOS", "10.15.7"));
+    assert!(!metadata.mobile);
+    let brands: Vec<_> = metadata.brands.unwrap().into_iter().map(|brand| (brand.brand, brand.version)).collect();
+    assert!(brands.contains(&("Google Chrome".to_string(), "119".to_string())), "{:?}", brands);
+    let full_versions = metadata.full_version_list.unwrap();
+    assert!(full_versions.iter().any(|brand| brand.brand == "Chromium" && brand.version == "119.0.6045.105"));
+
+    let mut windows = session("Win32", "7123456789012345678");
+    windows.user_agent = Some(FingerprintProfile::builtin()[0].user_agent.clone());
+    assert_eq!(stealth::user_agent_metadata(&windows).unwrap().platform, "Windows");
+
+    // Other browsers send no client hints
+    windows.user_agent = Some("Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0".to_string());
+    assert!(stealth::user_agent_metadata(&windows).is_none());
+}
+
+#[tokio::test]
+async fn test_sessions_present_a_consistent_user_agent() -> rust_tok::error::Result<()> {
+    let chrome = FakeChrome::start(Behaviour::default()).await;
+    // A profile captured from a headless browser still must not give it away
+    let mut profile = FingerprintProfile::builtin()[4].clone();
+    profile.user_agent = HEADLESS_USER_AGENT.to_string();
+    let mut config = TikTokApiConfig {
+        num_sessions: 1,
+        fingerprints: Some(vec![profile]),
+        health_check_interval: None,
+        warmup_timeout: Duration::from_millis(100),
+        ..Default::default()
+    };
+    config.launch.executable = Some(chrome.executable());
+    let api = TikTokApi::new(config).await?;
+
+    let overrides = chrome.calls("Network.setUserAgentOverride");
+    assert_eq!(overrides.len(), 1);
+    let params = &overrides[0].params;
+    assert_eq!(params["userAgent"], HEADLESS_USER_AGENT.replace("HeadlessChrome", "Chrome"));
+    assert_eq!(params["userAgentMetadata"]["platform"], "Linux");
+    let brands = params["userAgentMetadata"]["brands"].as_array().unwrap();
+    assert!(brands.iter().any(|brand| brand["brand"] == "Google Chrome" && brand["version"] == "120"), "{:?}", brands);
+
+    api.close().await?;
+    Ok(())
+}